@@ -0,0 +1,44 @@
+//! Times every generator separately from the solvers, on the bundled inputs.
+//!
+//! Compare across commits with criterion's baselines:
+//! `cargo bench --bench days -- --save-baseline main`, then
+//! `cargo bench --bench days -- --baseline main` on another commit.
+
+use std::{fs, path::Path};
+
+use aoc2021::registry::registry;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn read_input(day: u8) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("input/2021/day{}.txt", day));
+    fs::read_to_string(path)
+        .unwrap()
+        .trim_end_matches('\n')
+        .to_owned()
+}
+
+fn days(c: &mut Criterion) {
+    for day in 1..=8 {
+        let input = read_input(day);
+        let mut group = c.benchmark_group(format!("day{}", day));
+        for solution in registry().iter().filter(|s| s.day() == day) {
+            if solution.part() == 1 && solution.variant().is_none() {
+                group.bench_function("generator", |b| {
+                    b.iter(|| solution.generate(black_box(&input)).unwrap())
+                });
+            }
+            let parsed = solution.generate(&input).unwrap();
+            let name = match solution.variant() {
+                Some(variant) => format!("part{}-{}", solution.part(), variant),
+                None => format!("part{}", solution.part()),
+            };
+            group.bench_function(name, |b| {
+                b.iter(|| solution.solve(black_box(parsed.as_ref())))
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, days);
+criterion_main!(benches);
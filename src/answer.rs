@@ -0,0 +1,107 @@
+use std::{error::Error, fmt};
+
+/// What a solver returns, wide enough that custom inputs do not wrap.
+/// Numbers compare by value, whether `Unsigned` or `Signed`.
+#[derive(Debug, Clone, Eq)]
+pub enum Answer {
+    Unsigned(u128),
+    /// Built by `From` only for negative values, positive ones are `Unsigned`.
+    Signed(i128),
+    Text(String),
+    /// Rows of a rendered grid, e.g. letters drawn with `#` and `.`.
+    Grid(Vec<String>),
+}
+
+macro_rules! from_unsigned {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(v: $t) -> Self {
+                Answer::Unsigned(v as u128)
+            }
+        })*
+    };
+}
+
+macro_rules! from_signed {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(v: $t) -> Self {
+                if v < 0 {
+                    Answer::Signed(v as i128)
+                } else {
+                    Answer::Unsigned(v as u128)
+                }
+            }
+        })*
+    };
+}
+
+from_unsigned!(u8, u16, u32, u64, u128, usize);
+from_signed!(i8, i16, i32, i64, i128, isize);
+
+impl From<String> for Answer {
+    fn from(v: String) -> Self {
+        Answer::Text(v)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(v: &str) -> Self {
+        Answer::Text(v.to_owned())
+    }
+}
+
+impl PartialEq for Answer {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Answer::Unsigned(a), Answer::Unsigned(b)) => a == b,
+            (Answer::Signed(a), Answer::Signed(b)) => a == b,
+            (Answer::Unsigned(u), Answer::Signed(s)) | (Answer::Signed(s), Answer::Unsigned(u)) => {
+                u128::try_from(*s) == Ok(*u)
+            }
+            (Answer::Text(a), Answer::Text(b)) => a == b,
+            (Answer::Grid(a), Answer::Grid(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Unsigned(n) => write!(f, "{}", n),
+            Answer::Signed(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Grid(rows) => write!(f, "{}", rows.join("\n")),
+        }
+    }
+}
+
+/// Solvers return an `Answer`, or a `Result` of one when they can fail on a parsed input.
+pub trait IntoAnswer {
+    fn into_answer(self) -> Result<Answer, Box<dyn Error>>;
+}
+
+impl IntoAnswer for Answer {
+    fn into_answer(self) -> Result<Answer, Box<dyn Error>> {
+        Ok(self)
+    }
+}
+
+impl<E: Error + 'static> IntoAnswer for Result<Answer, E> {
+    fn into_answer(self) -> Result<Answer, Box<dyn Error>> {
+        self.map_err(|e| e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn numbers_compare_by_value() {
+        assert_eq!(Answer::Signed(5), Answer::from(5u8));
+        assert_eq!(Answer::from(-3i64), Answer::Signed(-3));
+        assert_ne!(Answer::Signed(-1), Answer::Unsigned(u128::MAX));
+        assert_ne!(Answer::from(5), Answer::from("5"));
+    }
+}
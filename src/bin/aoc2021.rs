@@ -0,0 +1,240 @@
+use std::{
+    env, fs,
+    io::{self, Read},
+    process,
+};
+
+use aoc2021::{
+    gen::{self, Rng},
+    registry::registry,
+    report::Report,
+};
+
+const USAGE: &str = "usage:
+    aoc2021 run --day N [--part P] [--input FILE] [--format FORMAT]
+    aoc2021 run --all [--part P] [--input-dir DIR] [--format FORMAT]
+    aoc2021 gen --day N [--count N] [--width W] [--seed S]
+
+Without --input, the puzzle input is read from stdin (also with `--input -`).
+With --all, inputs are read from DIR/dayN.txt (default: input/2021).
+FORMAT is one of text (default), json or csv; json and csv include timings and input hashes.
+
+gen prints a random input for day N: COUNT lines, boards or numbers, and WIDTH is
+the bit width (day 3), board size (day 4) or grid size (days 5 and 7).
+COUNT defaults to 1000; a COUNT of 0 gives an empty input.";
+
+#[derive(Default, PartialEq)]
+enum Command {
+    #[default]
+    Run,
+    Gen,
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Run => "run",
+            Command::Gen => "gen",
+        }
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        match self {
+            Command::Run => &[
+                "--all",
+                "--day",
+                "--part",
+                "--input",
+                "--input-dir",
+                "--format",
+            ],
+            Command::Gen => &["--day", "--count", "--width", "--seed"],
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Default)]
+struct Options {
+    command: Command,
+    all: bool,
+    day: Option<u8>,
+    part: Option<u8>,
+    input: Option<String>,
+    input_dir: Option<String>,
+    format: Format,
+    count: Option<usize>,
+    width: Option<usize>,
+    seed: Option<u64>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    match args.next().as_deref() {
+        Some("run") => options.command = Command::Run,
+        Some("gen") => options.command = Command::Gen,
+        Some(command) => return Err(format!("unknown command `{}`", command)),
+        None => return Err("missing command".to_owned()),
+    }
+    while let Some(arg) = args.next() {
+        if !options.command.options().contains(&arg.as_str())
+            && [Command::Run, Command::Gen]
+                .iter()
+                .any(|c| c.options().contains(&arg.as_str()))
+        {
+            return Err(format!(
+                "`{}` does not apply to `{}`",
+                arg,
+                options.command.name()
+            ));
+        }
+        let mut value = || args.next().ok_or(format!("missing value for `{}`", arg));
+        match arg.as_str() {
+            "--all" => options.all = true,
+            "--day" => options.day = Some(parse_number(&value()?, 1..=8, "day")?),
+            "--part" => options.part = Some(parse_number(&value()?, 1..=2, "part")?),
+            "--input" => options.input = Some(value()?),
+            "--input-dir" => options.input_dir = Some(value()?),
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    format => return Err(format!("unknown format `{}`", format)),
+                }
+            }
+            "--count" => options.count = Some(parse_value(&value()?, "count")?),
+            "--width" => options.width = Some(parse_value(&value()?, "width")?),
+            "--seed" => options.seed = Some(parse_value(&value()?, "seed")?),
+            _ => return Err(format!("unknown option `{}`", arg)),
+        }
+    }
+    match (&options.command, options.all, options.day) {
+        (Command::Gen, _, None) => Err("`gen` needs a `--day`".to_owned()),
+        (_, true, Some(_)) => Err("`--all` and `--day` are exclusive".to_owned()),
+        (_, true, _) if options.input.is_some() => {
+            Err("`--input` does not apply to `run --all`, see `--input-dir`".to_owned())
+        }
+        (_, false, _) if options.input_dir.is_some() => {
+            Err("`--input-dir` only applies to `run --all`".to_owned())
+        }
+        (_, false, None) => Err("one of `--all` or `--day` is required".to_owned()),
+        _ => Ok(options),
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} `{}`", name, value))
+}
+
+fn parse_number(
+    value: &str,
+    range: std::ops::RangeInclusive<u8>,
+    name: &str,
+) -> Result<u8, String> {
+    match value.parse() {
+        Ok(n) if range.contains(&n) => Ok(n),
+        _ => Err(format!(
+            "invalid {} `{}`, expected {}-{}",
+            name,
+            value,
+            range.start(),
+            range.end()
+        )),
+    }
+}
+
+fn read_input(path: Option<&str>) -> io::Result<String> {
+    let input = match path {
+        None | Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        }
+        Some(path) => fs::read_to_string(path)?,
+    };
+    // same as cargo-aoc, which strips the trailing newlines before calling generators
+    Ok(input.trim_end_matches('\n').to_owned())
+}
+
+fn run_day(day: u8, parts: &[u8], input: io::Result<String>) -> Vec<Report> {
+    registry()
+        .iter()
+        .filter(|s| s.day() == day && parts.contains(&s.part()))
+        .map(|solution| match &input {
+            Ok(input) => Report::run(*solution, input),
+            Err(e) => Report::failed(*solution, e.to_string()),
+        })
+        .collect()
+}
+
+fn print(reports: &[Report], format: Format) {
+    match format {
+        Format::Text => reports.iter().for_each(|r| println!("{}", r.to_text())),
+        Format::Json => {
+            let reports: Vec<_> = reports.iter().map(Report::to_json).collect();
+            println!("[\n{}\n]", reports.join(",\n"));
+        }
+        Format::Csv => {
+            println!("{}", Report::CSV_HEADER);
+            reports.iter().for_each(|r| println!("{}", r.to_csv()));
+        }
+    }
+}
+
+/// Returns whether every part succeeded.
+fn run(options: Options) -> bool {
+    let parts = match options.part {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
+    let reports = if options.all {
+        let dir = options.input_dir.as_deref().unwrap_or("input/2021");
+        (1..=8)
+            .flat_map(|day| {
+                let path = format!("{}/day{}.txt", dir, day);
+                let input = read_input(Some(&path))
+                    .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)));
+                run_day(day, &parts, input)
+            })
+            .collect()
+    } else {
+        let input = read_input(options.input.as_deref());
+        run_day(options.day.unwrap(), &parts, input)
+    };
+    print(&reports, options.format);
+    reports.iter().all(Report::is_ok)
+}
+
+fn generate(options: Options) {
+    let mut rng = Rng::new(options.seed.unwrap_or(2021));
+    let day = options.day.unwrap();
+    print!(
+        "{}",
+        gen::generate(day, &mut rng, options.count.unwrap_or(1000), options.width).unwrap()
+    );
+}
+
+fn main() {
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("error: {}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+    match options.command {
+        Command::Run => {
+            if !run(options) {
+                process::exit(1);
+            }
+        }
+        Command::Gen => generate(options),
+    }
+}
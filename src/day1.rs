@@ -1,21 +1,503 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::{self, Write},
+    io::{self, BufRead},
+};
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::{
+    answer::Answer,
+    error::ParseError,
+    input::{self, Line},
+};
+
 #[aoc_generator(day1)]
-pub fn input_generator(input: &str) -> Vec<u16> {
-    input.lines().map(|l| l.trim().parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<u16>, ParseError> {
+    input::lines(1, input)
+        .map(|line| {
+            let depth = line.text.trim_start();
+            depth.parse().map_err(|_| line.error(depth, "depth"))
+        })
+        .collect()
+}
+
+/// Sonar statistics updated one depth at a time, keeping only the last depths
+/// needed by the largest window.
+#[derive(Debug, Clone)]
+pub struct Sonar {
+    windows: Vec<usize>,
+    increases: Vec<usize>,
+    /// Depths kept in `recent`, enough for the largest window.
+    kept: usize,
+    recent: VecDeque<u32>,
+    count: usize,
+    min: Option<u32>,
+    max: Option<u32>,
+    run: usize,
+    longest_run: usize,
+}
+
+impl Sonar {
+    /// Counts increases for each of the window sizes.
+    pub fn new(windows: &[usize]) -> Self {
+        Self {
+            windows: windows.to_vec(),
+            increases: vec![0; windows.len()],
+            kept: windows.iter().copied().max().unwrap_or(0).max(1),
+            recent: VecDeque::new(),
+            count: 0,
+            min: None,
+            max: None,
+            run: 0,
+            longest_run: 0,
+        }
+    }
+
+    /// Compares the new depth with the one `window` depths before, as `count_increases` does.
+    pub fn push(&mut self, depth: u32) {
+        for (window, increases) in self.windows.iter().zip(self.increases.iter_mut()) {
+            if *window > 0
+                && self.recent.len() >= *window
+                && depth > self.recent[self.recent.len() - window]
+            {
+                *increases += 1;
+            }
+        }
+        self.run = match self.recent.back() {
+            Some(previous) if depth > *previous => self.run + 1,
+            _ => 1,
+        };
+        self.longest_run = self.longest_run.max(self.run);
+        self.min = Some(self.min.map_or(depth, |min| min.min(depth)));
+        self.max = Some(self.max.map_or(depth, |max| max.max(depth)));
+        self.count += 1;
+
+        self.recent.push_back(depth);
+        if self.recent.len() > self.kept {
+            self.recent.pop_front();
+        }
+    }
+
+    /// `None` if `window` was not given to `new`.
+    pub fn increases(&self, window: usize) -> Option<usize> {
+        self.windows
+            .iter()
+            .position(|w| *w == window)
+            .map(|i| self.increases[i])
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn min(&self) -> Option<u32> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<u32> {
+        self.max
+    }
+
+    /// Number of depths in the longest strictly increasing run.
+    pub fn longest_increasing_run(&self) -> usize {
+        self.longest_run
+    }
+}
+
+pub fn analyze(depths: impl IntoIterator<Item = u32>, windows: &[usize]) -> Sonar {
+    let mut sonar = Sonar::new(windows);
+    depths.into_iter().for_each(|depth| sonar.push(depth));
+    sonar
+}
+
+/// Same as `analyze`, reading one depth per line; a leading BOM and blank lines are skipped.
+/// Parse errors are returned as `InvalidData` errors wrapping a `ParseError`.
+pub fn analyze_reader(mut reader: impl BufRead, windows: &[usize]) -> io::Result<Sonar> {
+    let mut sonar = Sonar::new(windows);
+    let mut text = String::new();
+    let mut number = 0;
+    loop {
+        text.clear();
+        if reader.read_line(&mut text)? == 0 {
+            return Ok(sonar);
+        }
+        number += 1;
+        let text = match number {
+            1 => text.strip_prefix('\u{feff}').unwrap_or(&text),
+            _ => &text,
+        };
+        let line = Line {
+            day: 1,
+            number,
+            text: text.trim_end(),
+        };
+        let depth = line.text.trim_start();
+        if !depth.is_empty() {
+            let depth = depth.parse().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, line.error(depth, "depth"))
+            })?;
+            sonar.push(depth);
+        }
+    }
+}
+
+/// How many times the sum of `window` consecutive depths increases, see `Sonar::push`
+/// for depths that do not fit in memory.
+/// Two consecutive windows share all depths but their first and last ones, so comparing
+/// `depths[i]` with `depths[i + window]` is enough and no sum is ever computed.
+pub fn count_increases(depths: &[u16], window: usize) -> usize {
+    depths
+        .iter()
+        .zip(depths.iter().skip(window))
+        .filter(|(first, last)| last > first)
+        .count()
 }
 
 #[aoc(day1, part1)]
-pub fn solve_part1(input: &[u16]) -> u16 {
-    input.windows(2).map(|w| (w[1] > w[0]) as u16).sum()
+pub fn solve_part1(input: &[u16]) -> Answer {
+    count_increases(input, 1).into()
 }
 
 #[aoc(day1, part2)]
-pub fn solve_part2(input: &[u16]) -> u16 {
-    solve_part1(
-        &input
-            .windows(3)
-            .map(|w| w.iter().sum())
-            .collect::<Vec<u16>>(),
+pub fn solve_part2(input: &[u16]) -> Answer {
+    count_increases(input, 3).into()
+}
+
+/// Consecutive depths, `start` and `end` are inclusive indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Streak {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Streak {
+    /// Number of depths in the streak, at least 2.
+    pub fn depths(&self) -> usize {
+        self.end - self.start + 1
+    }
+}
+
+/// Maximal streaks of at least 2 depths where `holds` is true for every consecutive pair.
+fn streaks(depths: &[u16], holds: fn(u16, u16) -> bool) -> Vec<Streak> {
+    let mut streaks: Vec<Streak> = vec![];
+    for (i, w) in depths.windows(2).enumerate() {
+        if !holds(w[0], w[1]) {
+            continue;
+        }
+        match streaks.last_mut() {
+            Some(streak) if streak.end == i => streak.end = i + 1,
+            _ => streaks.push(Streak {
+                start: i,
+                end: i + 1,
+            }),
+        }
+    }
+    streaks
+}
+
+fn longest(streaks: Vec<Streak>) -> Option<Streak> {
+    streaks
+        .into_iter()
+        .fold(None, |longest: Option<Streak>, streak| match longest {
+            Some(l) if l.depths() >= streak.depths() => Some(l),
+            _ => Some(streak),
+        })
+}
+
+/// Change between the depths at `index` and `index + 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Jump {
+    pub index: usize,
+    pub delta: i32,
+}
+
+/// The shape of the seafloor, rather than just the number of increases.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthProfile {
+    pub longest_increase: Option<Streak>,
+    pub longest_decrease: Option<Streak>,
+    pub plateaus: Vec<Streak>,
+    /// Largest absolute change, the first one on ties.
+    pub largest_jump: Option<Jump>,
+    pub window: usize,
+    /// Average of each `window` consecutive depths.
+    pub moving_averages: Vec<f64>,
+    /// Number of consecutive depths by difference.
+    pub deltas: BTreeMap<i32, usize>,
+}
+
+impl DepthProfile {
+    pub fn new(depths: &[u16], window: usize) -> Self {
+        let jumps = depths.windows(2).enumerate().map(|(index, w)| Jump {
+            index,
+            delta: w[1] as i32 - w[0] as i32,
+        });
+        let mut deltas = BTreeMap::new();
+        for jump in jumps.clone() {
+            *deltas.entry(jump.delta).or_insert(0) += 1;
+        }
+        Self {
+            longest_increase: longest(streaks(depths, |a, b| b > a)),
+            longest_decrease: longest(streaks(depths, |a, b| b < a)),
+            plateaus: streaks(depths, |a, b| b == a),
+            largest_jump: jumps.fold(None, |largest: Option<Jump>, jump| match largest {
+                Some(l) if l.delta.abs() >= jump.delta.abs() => Some(l),
+                _ => Some(jump),
+            }),
+            window,
+            moving_averages: depths
+                .windows(window.max(1))
+                .map(|w| w.iter().map(|d| *d as f64).sum::<f64>() / w.len() as f64)
+                .collect(),
+            deltas,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let streak = |s: &Option<Streak>| match s {
+            Some(s) => format!("{{\"start\":{},\"end\":{}}}", s.start, s.end),
+            None => "null".to_owned(),
+        };
+        let plateaus: Vec<String> = self.plateaus.iter().map(|p| streak(&Some(*p))).collect();
+        let jump = match self.largest_jump {
+            Some(j) => format!("{{\"index\":{},\"delta\":{}}}", j.index, j.delta),
+            None => "null".to_owned(),
+        };
+        let averages: Vec<String> = self.moving_averages.iter().map(f64::to_string).collect();
+        let deltas: Vec<String> = self
+            .deltas
+            .iter()
+            .map(|(delta, count)| format!("\"{}\":{}", delta, count))
+            .collect();
+        format!(
+            "{{\"longest_increase\":{},\"longest_decrease\":{},\"plateaus\":[{}],\"largest_jump\":{},\"window\":{},\"moving_averages\":[{}],\"deltas\":{{{}}}}}",
+            streak(&self.longest_increase),
+            streak(&self.longest_decrease),
+            plateaus.join(","),
+            jump,
+            self.window,
+            averages.join(","),
+            deltas.join(",")
+        )
+    }
+}
+
+impl fmt::Display for DepthProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let streak = |s: &Option<Streak>| match s {
+            Some(s) => format!("{} depths, {}..={}", s.depths(), s.start, s.end),
+            None => "none".to_owned(),
+        };
+        writeln!(f, "longest increase: {}", streak(&self.longest_increase))?;
+        writeln!(f, "longest decrease: {}", streak(&self.longest_decrease))?;
+        writeln!(f, "plateaus: {}", self.plateaus.len())?;
+        for p in &self.plateaus {
+            writeln!(f, "  {}", streak(&Some(*p)))?;
+        }
+        match self.largest_jump {
+            Some(j) => writeln!(f, "largest jump: {:+} at {}", j.delta, j.index)?,
+            None => writeln!(f, "largest jump: none")?,
+        }
+        let averages = self.moving_averages.iter().copied();
+        match (averages.clone().reduce(f64::min), averages.reduce(f64::max)) {
+            (Some(min), Some(max)) => writeln!(
+                f,
+                "moving average ({} depths): {:.2} to {:.2}",
+                self.window, min, max
+            )?,
+            _ => writeln!(f, "moving average ({} depths): none", self.window)?,
+        }
+        writeln!(f, "deltas:")?;
+        for (delta, count) in &self.deltas {
+            writeln!(f, "  {:+}: {}", delta, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// `increased[i]` tells whether the window ending on depth `i` has a larger sum than the
+/// previous one, as counted by `count_increases`.
+fn increased(depths: &[u16], window: usize) -> Vec<bool> {
+    (0..depths.len())
+        .map(|i| window > 0 && i >= window && depths[i] > depths[i - window])
+        .collect()
+}
+
+/// One block per depth, higher blocks are deeper.
+pub fn sparkline(depths: &[u16]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let min = depths.iter().copied().min().unwrap_or(0) as usize;
+    let max = depths.iter().copied().max().unwrap_or(0) as usize;
+    depths
+        .iter()
+        .map(|d| BLOCKS[(*d as usize - min) * 7 / (max - min).max(1)])
+        .collect()
+}
+
+/// One column per depth, the surface at the top. Depths deeper than the previous one
+/// (part 1 increases) are drawn `+`, the others `o`; the last line has a `^` under
+/// each depth ending a 3 depths window larger than the previous one (part 2 increases).
+pub fn ascii_chart(depths: &[u16], height: usize) -> String {
+    let height = height.max(2);
+    let min = depths.iter().copied().min().unwrap_or(0) as usize;
+    let max = depths.iter().copied().max().unwrap_or(0) as usize;
+    let row = |d: u16| (d as usize - min) * (height - 1) / (max - min).max(1);
+    let part1 = increased(depths, 1);
+    let part2 = increased(depths, 3);
+    let mut chart = String::new();
+    for r in 0..height {
+        let line: String = depths
+            .iter()
+            .zip(&part1)
+            .map(|(d, up)| match (row(*d) == r, up) {
+                (false, _) => ' ',
+                (true, true) => '+',
+                (true, false) => 'o',
+            })
+            .collect();
+        writeln!(chart, "{}", line.trim_end()).unwrap();
+    }
+    let line: String = part2.iter().map(|up| if *up { '^' } else { ' ' }).collect();
+    writeln!(chart, "{}", line.trim_end()).unwrap();
+    chart
+}
+
+/// Line chart of the depths, the surface at the top. Part 1 increases are red dots,
+/// the dashed line is the average of each 3 depths window, with blue dots on the
+/// windows counted by part 2.
+pub fn svg_chart(depths: &[u16], width: usize, height: usize) -> String {
+    let min = depths.iter().copied().min().unwrap_or(0) as f64;
+    let max = depths.iter().copied().max().unwrap_or(0) as f64;
+    let x = |i: usize| i as f64 * width as f64 / (depths.len().max(2) - 1) as f64;
+    let y = |d: f64| (d - min) * height as f64 / (max - min).max(1.0);
+    let points = |values: &mut dyn Iterator<Item = (usize, f64)>| {
+        values
+            .map(|(i, d)| format!("{:.1},{:.1}", x(i), y(d)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let averages: Vec<(usize, f64)> = depths
+        .windows(3)
+        .enumerate()
+        .map(|(i, w)| (i + 2, w.iter().map(|d| *d as f64).sum::<f64>() / 3.0))
+        .collect();
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-5 -5 {} {}\">\n",
+        width + 10,
+        height + 10
+    );
+    writeln!(
+        svg,
+        "<polyline fill=\"none\" stroke=\"black\" points=\"{}\"/>",
+        points(&mut depths.iter().map(|d| *d as f64).enumerate())
     )
+    .unwrap();
+    writeln!(
+        svg,
+        "<polyline fill=\"none\" stroke=\"blue\" stroke-dasharray=\"4\" points=\"{}\"/>",
+        points(&mut averages.iter().copied())
+    )
+    .unwrap();
+    for (i, up) in increased(depths, 1).into_iter().enumerate() {
+        if up {
+            writeln!(
+                svg,
+                "<circle class=\"part1\" cx=\"{:.1}\" cy=\"{:.1}\" r=\"2\" fill=\"red\"/>",
+                x(i),
+                y(depths[i] as f64)
+            )
+            .unwrap();
+        }
+    }
+    for (i, average) in averages.iter().skip(1) {
+        if depths[*i] > depths[i - 3] {
+            writeln!(
+                svg,
+                "<circle class=\"part2\" cx=\"{:.1}\" cy=\"{:.1}\" r=\"2\" fill=\"blue\"/>",
+                x(*i),
+                y(*average)
+            )
+            .unwrap();
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    #[test]
+    fn d1_stream() {
+        let input = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+        let sonar = analyze_reader(input.as_bytes(), &[1, 3, 100]).unwrap();
+        assert_eq!(sonar.increases(1), Some(7));
+        assert_eq!(sonar.increases(3), Some(5));
+        assert_eq!(sonar.increases(100), Some(0));
+        assert_eq!((sonar.min(), sonar.max()), (Some(199), Some(269)));
+        assert_eq!(sonar.longest_increasing_run(), 4);
+        let error = analyze_reader("1\n2\n-3\n".as_bytes(), &[1]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "day1 line 3 column 1: expected `depth`, found `-3`"
+        );
+        let input = "\u{feff}199\r\n200\r\n";
+        let sonar = analyze_reader(input.as_bytes(), &[1]).unwrap();
+        assert_eq!(sonar.increases(1), Some(1));
+        assert_eq!(sonar.count(), 2);
+    }
+    #[test]
+    fn d1_profile() {
+        let depths = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263, 263, 263];
+        let profile = DepthProfile::new(&depths, 3);
+        assert_eq!(profile.longest_increase, Some(Streak { start: 0, end: 3 }));
+        assert_eq!(profile.longest_decrease, Some(Streak { start: 3, end: 4 }));
+        assert_eq!(profile.plateaus, vec![Streak { start: 9, end: 11 }]);
+        assert_eq!(
+            profile.largest_jump,
+            Some(Jump {
+                index: 5,
+                delta: 33
+            })
+        );
+        assert_eq!(profile.moving_averages[0], 607.0 / 3.0);
+        assert_eq!(profile.deltas[&0], 2);
+        let short = DepthProfile::new(&[1, 2], 3).to_string();
+        assert!(
+            short.contains("moving average (3 depths): none\n"),
+            "{}",
+            short
+        );
+    }
+    #[test]
+    fn d1_charts() {
+        let depths = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(sparkline(&depths), "▁▁▁▂▁▁▅█▇▇");
+        assert_eq!(
+            ascii_chart(&depths, 3),
+            "o+++o+\n      + o+\n       +\n   ^  ^^^^\n"
+        );
+        let svg = svg_chart(&depths, 100, 50);
+        assert_eq!(svg.matches("class=\"part1\"").count(), 7);
+        assert_eq!(svg.matches("class=\"part2\"").count(), 5);
+    }
+    proptest! {
+        #[test]
+        fn d1_endpoints_compare_like_window_sums(
+            depths in prop::collection::vec(any::<u16>(), 0..200),
+            window in 1..10usize,
+        ) {
+            let sums: Vec<u32> = depths
+                .windows(window)
+                .map(|w| w.iter().map(|d| *d as u32).sum())
+                .collect();
+            let increases = sums.windows(2).filter(|w| w[1] > w[0]).count();
+            prop_assert_eq!(count_increases(&depths, window), increases);
+            let sonar = analyze(depths.iter().map(|d| *d as u32), &[window]);
+            prop_assert_eq!(sonar.increases(window), Some(increases));
+        }
+    }
 }
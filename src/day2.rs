@@ -1,100 +1,851 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::ops::Add;
+use std::{collections::HashMap, error::Error, fmt, fmt::Write, str::FromStr};
 
+use crate::{
+    answer::Answer,
+    error::ParseError,
+    input::{self, Line},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Command {
-    Forward(u8),
-    Down(u8),
-    Up(u8),
+    Forward(u32),
+    Down(u32),
+    Up(u32),
+    /// Opposite of `Forward`.
+    Back(u32),
+    /// Goes down whatever the aim.
+    Dive(u32),
+    /// Back to depth 0.
+    Surface,
+    /// Sets the aim, which may be negative to point up.
+    Turn(i64),
+}
+
+/// `aim` is only used by aim based navigations. Depth grows downward.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub horizontal: i64,
+    pub depth: i64,
+    pub aim: i64,
+}
+
+/// How a command moves the submarine, `None` on overflow. Closures taking a position
+/// and a command can be used for custom interpretations.
+/// The depth may become negative, `Submarine` decides what to do about it.
+pub trait Navigation {
+    fn step(&self, position: Position, command: &Command) -> Option<Position>;
 }
 
-#[derive(Default)]
-struct Position {
-    horizontal: u32,
-    depth: u32,
+impl<F: Fn(Position, &Command) -> Option<Position>> Navigation for F {
+    fn step(&self, position: Position, command: &Command) -> Option<Position> {
+        self(position, command)
+    }
+}
+
+/// Part 1: `down` and `up` change the depth directly.
+pub struct Direct;
+
+impl Navigation for Direct {
+    fn step(&self, position: Position, command: &Command) -> Option<Position> {
+        Some(match *command {
+            Command::Forward(x) => Position {
+                horizontal: position.horizontal.checked_add(x as i64)?,
+                ..position
+            },
+            Command::Back(x) => Position {
+                horizontal: position.horizontal.checked_sub(x as i64)?,
+                ..position
+            },
+            Command::Down(x) | Command::Dive(x) => Position {
+                depth: position.depth.checked_add(x as i64)?,
+                ..position
+            },
+            Command::Up(x) => Position {
+                depth: position.depth.checked_sub(x as i64)?,
+                ..position
+            },
+            Command::Surface => Position {
+                depth: 0,
+                ..position
+            },
+            Command::Turn(aim) => Position { aim, ..position },
+        })
+    }
 }
 
-impl Add<&Command> for Position {
-    type Output = Position;
+/// Part 2: `down` and `up` change the aim, `forward` and `back` move along it.
+pub struct Aimed;
 
-    fn add(self, _rhs: &Command) -> Position {
-        match _rhs {
+impl Navigation for Aimed {
+    fn step(&self, position: Position, command: &Command) -> Option<Position> {
+        Some(match *command {
             Command::Forward(x) => Position {
-                horizontal: self.horizontal + *x as u32,
-                depth: self.depth,
+                horizontal: position.horizontal.checked_add(x as i64)?,
+                depth: position
+                    .depth
+                    .checked_add(position.aim.checked_mul(x as i64)?)?,
+                ..position
+            },
+            Command::Back(x) => Position {
+                horizontal: position.horizontal.checked_sub(x as i64)?,
+                depth: position
+                    .depth
+                    .checked_sub(position.aim.checked_mul(x as i64)?)?,
+                ..position
             },
             Command::Down(x) => Position {
-                horizontal: self.horizontal,
-                depth: self.depth + *x as u32,
+                aim: position.aim.checked_add(x as i64)?,
+                ..position
             },
             Command::Up(x) => Position {
-                horizontal: self.horizontal,
-                depth: self.depth - *x as u32,
+                aim: position.aim.checked_sub(x as i64)?,
+                ..position
+            },
+            Command::Dive(x) => Position {
+                depth: position.depth.checked_add(x as i64)?,
+                ..position
+            },
+            Command::Surface => Position {
+                depth: 0,
+                ..position
             },
+            Command::Turn(aim) => Position { aim, ..position },
+        })
+    }
+}
+
+/// What to do when a command would take the submarine above the surface.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceMode {
+    /// Stop at depth 0.
+    Clamp,
+    #[default]
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationErrorKind {
+    Overflow,
+    AboveSurface,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NavigationError {
+    /// 0-based index of the failing command.
+    pub step: usize,
+    pub command: Command,
+    pub kind: NavigationErrorKind,
+}
+
+impl fmt::Display for NavigationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            NavigationErrorKind::Overflow => "overflows",
+            NavigationErrorKind::AboveSurface => "goes above the surface",
+        };
+        write!(
+            f,
+            "command {} ({:?}) {}",
+            self.step + 1,
+            self.command,
+            reason
+        )
+    }
+}
+
+impl Error for NavigationError {}
+
+pub struct Submarine<N> {
+    navigation: N,
+    surface_mode: SurfaceMode,
+    position: Position,
+    steps: usize,
+}
+
+impl<N: Navigation> Submarine<N> {
+    /// At the surface, failing on commands going above it.
+    pub fn new(navigation: N) -> Self {
+        Self {
+            navigation,
+            surface_mode: SurfaceMode::default(),
+            position: Position::default(),
+            steps: 0,
         }
     }
+
+    pub fn with_surface_mode(mut self, surface_mode: SurfaceMode) -> Self {
+        self.surface_mode = surface_mode;
+        self
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// On error, the submarine stays where it was.
+    pub fn execute(&mut self, command: &Command) -> Result<Position, NavigationError> {
+        let error = |kind| NavigationError {
+            step: self.steps,
+            command: *command,
+            kind,
+        };
+        let mut position = self
+            .navigation
+            .step(self.position, command)
+            .ok_or_else(|| error(NavigationErrorKind::Overflow))?;
+        if position.depth < 0 {
+            match self.surface_mode {
+                SurfaceMode::Clamp => position.depth = 0,
+                SurfaceMode::Error => return Err(error(NavigationErrorKind::AboveSurface)),
+            }
+        }
+        self.position = position;
+        self.steps += 1;
+        Ok(position)
+    }
+
+    /// Final position after every command.
+    pub fn run(&mut self, commands: &[Command]) -> Result<Position, NavigationError> {
+        for command in commands {
+            self.execute(command)?;
+        }
+        Ok(self.position)
+    }
+
+    /// Records every command with the position it led to, starting from the current one.
+    pub fn trajectory(&mut self, commands: &[Command]) -> Result<Trajectory, NavigationError> {
+        let mut trajectory = Trajectory::new(self.position);
+        for command in commands {
+            trajectory.steps.push(Step {
+                command: *command,
+                position: self.execute(command)?,
+            });
+        }
+        Ok(trajectory)
+    }
+}
+
+/// A command and the position it led to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub command: Command,
+    pub position: Position,
 }
 
-#[derive(Default)]
-struct Position2 {
-    horizontal: u32,
-    depth: u32,
-    aim: u32,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trajectory {
+    pub start: Position,
+    pub steps: Vec<Step>,
 }
 
-impl Add<&Command> for Position2 {
-    type Output = Position2;
+impl Trajectory {
+    pub fn new(start: Position) -> Self {
+        Self {
+            start,
+            steps: vec![],
+        }
+    }
 
-    fn add(self, _rhs: &Command) -> Position2 {
-        match _rhs {
-            Command::Forward(x) => Position2 {
-                horizontal: self.horizontal + *x as u32,
-                depth: self.depth + self.aim * *x as u32,
-                aim: self.aim,
-            },
-            Command::Down(x) => Position2 {
-                horizontal: self.horizontal,
-                depth: self.depth,
-                aim: self.aim + *x as u32,
-            },
-            Command::Up(x) => Position2 {
-                horizontal: self.horizontal,
-                depth: self.depth,
-                aim: self.aim - *x as u32,
+    pub fn end(&self) -> Position {
+        self.steps.last().map_or(self.start, |step| step.position)
+    }
+
+    /// The start and then one position per step.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        std::iter::once(self.start).chain(self.steps.iter().map(|step| step.position))
+    }
+
+    /// Every step as `(before, command, after)`.
+    pub fn replay(&self) -> impl Iterator<Item = (Position, &Command, Position)> {
+        self.positions()
+            .zip(&self.steps)
+            .map(|(before, step)| (before, &step.command, step.position))
+    }
+
+    /// To run the same route with another navigation.
+    pub fn commands(&self) -> Vec<Command> {
+        self.steps.iter().map(|step| step.command).collect()
+    }
+
+    pub fn max_depth(&self) -> i64 {
+        self.positions().map(|p| p.depth).max().unwrap()
+    }
+
+    /// Index of the first step going from above `depth` to `depth` or below.
+    pub fn first_crossing(&self, depth: i64) -> Option<usize> {
+        self.replay()
+            .position(|(before, _, after)| before.depth < depth && after.depth >= depth)
+    }
+
+    /// Length of the path, moving in a straight line at each step.
+    pub fn distance(&self) -> f64 {
+        self.replay()
+            .map(|(before, _, after)| {
+                let dx = (after.horizontal - before.horizontal) as f64;
+                let dy = (after.depth - before.depth) as f64;
+                dx.hypot(dy)
+            })
+            .sum()
+    }
+
+    pub const CSV_HEADER: &'static str = "step,command,horizontal,depth,aim";
+
+    /// One row per position, the start being step 0 without a command.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", Self::CSV_HEADER);
+        let commands = std::iter::once(None).chain(self.steps.iter().map(|s| Some(s.command)));
+        for (i, (command, p)) in commands.zip(self.positions()).enumerate() {
+            let command = command.map(|c| c.to_string()).unwrap_or_default();
+            writeln!(
+                csv,
+                "{},{},{},{},{}",
+                i, command, p.horizontal, p.depth, p.aim
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    /// Side view of the path, the surface at the top. Each step is a dot whose tooltip
+    /// gives its number and command.
+    pub fn to_svg(&self, width: usize, height: usize) -> String {
+        let positions: Vec<Position> = self.positions().collect();
+        let min_x = positions.iter().map(|p| p.horizontal).min().unwrap() as f64;
+        let max_x = positions.iter().map(|p| p.horizontal).max().unwrap() as f64;
+        let min_y = positions.iter().map(|p| p.depth).min().unwrap().min(0) as f64;
+        let max_y = self.max_depth() as f64;
+        let x =
+            |p: &Position| (p.horizontal as f64 - min_x) * width as f64 / (max_x - min_x).max(1.0);
+        let y = |p: &Position| (p.depth as f64 - min_y) * height as f64 / (max_y - min_y).max(1.0);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-5 -5 {} {}\">\n",
+            width + 10,
+            height + 10
+        );
+        writeln!(
+            svg,
+            "<line stroke=\"lightblue\" x1=\"0\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\"/>",
+            y(&Position::default()),
+            width,
+            y(&Position::default())
+        )
+        .unwrap();
+        let points: Vec<String> = positions
+            .iter()
+            .map(|p| format!("{:.1},{:.1}", x(p), y(p)))
+            .collect();
+        writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"black\" points=\"{}\"/>",
+            points.join(" ")
+        )
+        .unwrap();
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(
+                svg,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"2\" fill=\"red\"><title>{}: {}</title></circle>",
+                x(&step.position),
+                y(&step.position),
+                i + 1,
+                step.command
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl fmt::Display for Command {
+    /// Same format as the input.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Forward(x) => write!(f, "forward {}", x),
+            Command::Down(x) => write!(f, "down {}", x),
+            Command::Up(x) => write!(f, "up {}", x),
+            Command::Back(x) => write!(f, "back {}", x),
+            Command::Dive(x) => write!(f, "dive {}", x),
+            Command::Surface => write!(f, "surface"),
+            Command::Turn(aim) => write!(f, "turn {}", aim),
+        }
+    }
+}
+
+const VERBS: &str = "forward|down|up|back|dive|surface|turn";
+
+impl FromStr for Command {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let verb = words.next().unwrap_or(s);
+        let amount = words.next();
+        if let Some(extra) = words.next() {
+            return Err(ParseError::new(s, extra, "end of line"));
+        }
+        let x = || {
+            let amount = amount.ok_or_else(|| ParseError::new(s, &s[s.len()..], "amount"))?;
+            amount
+                .parse()
+                .map_err(|_| ParseError::new(s, amount, "amount"))
+        };
+        match verb {
+            "forward" => Ok(Command::Forward(x()?)),
+            "down" => Ok(Command::Down(x()?)),
+            "up" => Ok(Command::Up(x()?)),
+            "back" => Ok(Command::Back(x()?)),
+            "dive" => Ok(Command::Dive(x()?)),
+            "surface" => match amount {
+                Some(amount) => Err(ParseError::new(s, amount, "end of line")),
+                None => Ok(Command::Surface),
             },
+            "turn" => {
+                let aim = amount.ok_or_else(|| ParseError::new(s, &s[s.len()..], "aim"))?;
+                Ok(Command::Turn(
+                    aim.parse().map_err(|_| ParseError::new(s, aim, "aim"))?,
+                ))
+            }
+            _ => Err(ParseError::new(s, verb, VERBS)),
         }
     }
 }
 
-#[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<Command> {
-    input
-        .lines()
-        .map(|l| {
-            let command: Vec<&str> = l.trim().split(" ").collect();
-            match command[0] {
-                "forward" => Command::Forward(command[1].parse().unwrap()),
-                "down" => Command::Down(command[1].parse().unwrap()),
-                "up" => Command::Up(command[1].parse().unwrap()),
-                _ => unreachable!(),
+/// A statement of a route script, see `expand_script`.
+#[derive(Debug)]
+enum Statement<'a> {
+    /// Kept as text until expansion, the amount may be a variable.
+    Command(Line<'a>),
+    Let {
+        line: Line<'a>,
+        name: &'a str,
+        value: &'a str,
+    },
+    Repeat {
+        line: Line<'a>,
+        count: &'a str,
+        body: Vec<Statement<'a>>,
+    },
+    Call {
+        line: Line<'a>,
+        name: &'a str,
+    },
+}
+
+fn is_verb(word: &str) -> bool {
+    VERBS.split('|').any(|verb| verb == word)
+}
+
+fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !matches!(word, "let" | "macro" | "repeat")
+        && !is_verb(word)
+}
+
+/// Statements until the `}` closing `opening`, or until the end of the script at the top level.
+fn parse_block<'a>(
+    lines: &mut impl Iterator<Item = Line<'a>>,
+    macros: &mut HashMap<&'a str, Vec<Statement<'a>>>,
+    opening: Option<(Line<'a>, &'a str)>,
+) -> Result<Vec<Statement<'a>>, ParseError> {
+    let mut statements = vec![];
+    while let Some(line) = lines.next() {
+        let line = Line {
+            text: line.text.split('#').next().unwrap().trim_end(),
+            ..line
+        };
+        let words: Vec<&str> = line.text.split_whitespace().collect();
+        let statement = match words[..] {
+            [] => continue,
+            ["}"] if opening.is_some() => return Ok(statements),
+            ["}"] => return Err(line.error(words[0], "statement")),
+            ["let", name, "=", value] if is_identifier(name) => {
+                Statement::Let { line, name, value }
             }
-        })
-        .collect()
+            ["let", name, ..] if !is_identifier(name) => {
+                return Err(line.error(name, "variable name"))
+            }
+            ["let", _, ..] => return Err(line.error(line.text, "let <name> = <value>")),
+            ["macro", name, "{"] if is_identifier(name) => {
+                let body = parse_block(lines, macros, Some((line, words[2])))?;
+                if macros.insert(name, body).is_some() {
+                    return Err(line.error(name, "new macro name"));
+                }
+                continue;
+            }
+            ["macro", name, ..] if !is_identifier(name) => {
+                return Err(line.error(name, "macro name"))
+            }
+            ["macro", ..] => return Err(line.error(line.text, "macro <name> {")),
+            ["repeat", count, "{"] => Statement::Repeat {
+                line,
+                count,
+                body: parse_block(lines, macros, Some((line, words[2])))?,
+            },
+            ["repeat", ..] => return Err(line.error(line.text, "repeat <count> {")),
+            [name] if !is_verb(name) => Statement::Call { line, name },
+            _ => Statement::Command(line),
+        };
+        statements.push(statement);
+    }
+    match opening {
+        Some((line, brace)) => Err(line.error(brace, "matching }")),
+        None => Ok(statements),
+    }
+}
+
+/// Most steps, commands or repeat iterations, a script may expand to, so that nested repeats and
+/// macros fail instead of running for ever.
+const MAX_EXPANDED: usize = 1_000_000;
+
+struct Expansion<'a, 'm> {
+    macros: &'m HashMap<&'a str, Vec<Statement<'a>>>,
+    variables: HashMap<&'a str, i64>,
+    /// Macros being expanded, to reject recursion.
+    calls: Vec<&'a str>,
+    /// The top level `repeat` or macro call being expanded, blamed when the expansion is too long.
+    within: Option<(Line<'a>, &'a str)>,
+    /// Commands pushed and repeat iterations run so far.
+    expanded: usize,
+    commands: Vec<Command>,
 }
 
-pub fn solve<'a, T: Add<&'a Command, Output = T> + Default>(input: &'a [Command]) -> T {
-    input
-        .iter()
-        .fold(T::default(), |acc, command| acc + command)
+impl<'a, 'm> Expansion<'a, 'm> {
+    /// A number or a `$variable`.
+    fn value(&self, line: &Line, token: &str) -> Result<i64, ParseError> {
+        match token.strip_prefix('$') {
+            Some(name) => self
+                .variables
+                .get(name)
+                .copied()
+                .ok_or_else(|| line.error(token, "defined variable")),
+            None => token
+                .parse()
+                .map_err(|_| line.error(token, "number or $variable")),
+        }
+    }
+
+    /// Counts one more step of the expansion against `MAX_EXPANDED`.
+    fn expand(&mut self, line: Line<'a>, token: &'a str) -> Result<(), ParseError> {
+        self.expanded += 1;
+        if self.expanded > MAX_EXPANDED {
+            let (line, token) = self.within.unwrap_or((line, token));
+            return Err(line.error(token, "expansion of at most 1000000 steps"));
+        }
+        Ok(())
+    }
+
+    fn run(&mut self, statements: &'m [Statement<'a>]) -> Result<(), ParseError> {
+        for statement in statements {
+            match statement {
+                Statement::Command(line) => {
+                    let mut words = line.text.split_whitespace();
+                    let command = match (words.next(), words.next()) {
+                        (Some(verb), Some(amount)) if is_verb(verb) && amount.starts_with('$') => {
+                            let text = format!("{} {}", verb, self.value(line, amount)?);
+                            text.parse().map_err(|_| line.error(amount, "amount"))?
+                        }
+                        _ => line.parse()?,
+                    };
+                    self.expand(*line, line.text.trim_start())?;
+                    self.commands.push(command);
+                }
+                Statement::Let { line, name, value } => {
+                    let value = self.value(line, value)?;
+                    self.variables.insert(name, value);
+                }
+                Statement::Repeat { line, count, body } => {
+                    let n = self.value(line, count)?;
+                    if n < 0 {
+                        return Err(line.error(count, "repeat count"));
+                    }
+                    let outermost = self.within.is_none();
+                    if outermost {
+                        self.within = Some((*line, count));
+                    }
+                    for _ in 0..n {
+                        self.expand(*line, count)?;
+                        self.run(body)?;
+                    }
+                    if outermost {
+                        self.within = None;
+                    }
+                }
+                Statement::Call { line, name } => {
+                    if self.calls.contains(name) {
+                        return Err(line.error(name, "non-recursive macro"));
+                    }
+                    let body = self
+                        .macros
+                        .get(name)
+                        .ok_or_else(|| line.error(name, "command or macro"))?;
+                    let outermost = self.within.is_none();
+                    if outermost {
+                        self.within = Some((*line, name));
+                    }
+                    self.calls.push(name);
+                    self.run(body)?;
+                    self.calls.pop();
+                    if outermost {
+                        self.within = None;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Expands a route script to plain commands. On top of one command per line, a script has:
+/// - `# comments` until the end of the line,
+/// - variables, set with `let depth = 5` and used in place of an amount or a count as `$depth`,
+/// - `repeat 3 {`, up to a `}` on its own line,
+/// - `macro name {` up to a `}`, expanded where `name` is used alone on a line.
+///
+/// Variables are global and set in order of expansion, macros can be used before their definition.
+/// A script expands to at most a million steps, counting commands and repeat iterations.
+pub fn expand_script(input: &str) -> Result<Vec<Command>, ParseError> {
+    let mut macros = HashMap::new();
+    let statements = parse_block(&mut input::lines(2, input), &mut macros, None)?;
+    let mut expansion = Expansion {
+        macros: &macros,
+        variables: HashMap::new(),
+        calls: vec![],
+        within: None,
+        expanded: 0,
+        commands: vec![],
+    };
+    expansion.run(&statements)?;
+    Ok(expansion.commands)
+}
+
+#[aoc_generator(day2)]
+pub fn input_generator(input: &str) -> Result<Vec<Command>, ParseError> {
+    expand_script(input)
 }
 
 #[aoc(day2, part1)]
-pub fn solve_part1(input: &[Command]) -> u64 {
-    let position = solve::<Position>(input);
-    (position.horizontal * position.depth).into()
+pub fn solve_part1(input: &[Command]) -> Result<Answer, NavigationError> {
+    let position = Submarine::new(Direct).run(input)?;
+    Ok((position.horizontal as i128 * position.depth as i128).into())
 }
 
 #[aoc(day2, part2)]
-pub fn solve_part2(input: &[Command]) -> u64 {
-    let position = solve::<Position2>(input);
-    (position.horizontal * position.depth).into()
+pub fn solve_part2(input: &[Command]) -> Result<Answer, NavigationError> {
+    let position = Submarine::new(Aimed).run(input)?;
+    Ok((position.horizontal as i128 * position.depth as i128).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    const EXAMPLE: &str = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2";
+    #[test]
+    fn d2_parts() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1(&input).unwrap(), 150.into());
+        assert_eq!(solve_part2(&input).unwrap(), 900.into());
+    }
+    #[test]
+    fn d2_custom_navigation() {
+        let input = input_generator(EXAMPLE).unwrap();
+        // forward also dives by half its amount
+        let diving = |position: Position, command: &Command| match command {
+            Command::Forward(x) => Some(Position {
+                depth: position.depth + *x as i64 / 2,
+                ..Direct.step(position, command)?
+            }),
+            _ => Direct.step(position, command),
+        };
+        let trajectory = Submarine::new(diving).trajectory(&input).unwrap();
+        assert_eq!(trajectory.steps.len(), input.len());
+        assert_eq!(trajectory.start, Position::default());
+        assert_eq!(trajectory.end().depth, 10 + 2 + 4 + 1);
+    }
+    #[test]
+    fn d2_trajectory() {
+        let input = input_generator(EXAMPLE).unwrap();
+        let trajectory = Submarine::new(Aimed).trajectory(&input).unwrap();
+        assert_eq!(trajectory.max_depth(), 60);
+        assert_eq!(trajectory.first_crossing(1), Some(2));
+        assert_eq!(trajectory.first_crossing(61), None);
+        assert_eq!(
+            trajectory.distance(),
+            5.0 + 40f64.hypot(8.0) + 20f64.hypot(2.0)
+        );
+        let replayed = Submarine::new(Aimed)
+            .trajectory(&trajectory.commands())
+            .unwrap();
+        assert_eq!(replayed, trajectory);
+        let csv = trajectory.to_csv();
+        assert!(csv.starts_with("step,command,horizontal,depth,aim\n0,,0,0,0\n1,forward 5,5,0,0\n"));
+        assert!(csv.ends_with("6,forward 2,15,60,10\n"));
+        let svg = trajectory.to_svg(150, 60);
+        assert!(svg.contains(
+            "points=\"0.0,0.0 50.0,0.0 50.0,0.0 130.0,40.0 130.0,40.0 130.0,40.0 150.0,60.0\""
+        ));
+        assert!(svg.contains("<title>3: forward 8</title>"));
+    }
+    #[test]
+    fn d2_surface() {
+        let input =
+            input_generator("forward 300\ndown 2\nup 5\nback 100\nturn -3\nsurface").unwrap();
+        let error = Submarine::new(Direct).run(&input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "command 3 (Up(5)) goes above the surface"
+        );
+        let position = Submarine::new(Direct)
+            .with_surface_mode(SurfaceMode::Clamp)
+            .run(&input[..5])
+            .unwrap();
+        assert_eq!(
+            position,
+            Position {
+                horizontal: 200,
+                depth: 0,
+                aim: -3
+            }
+        );
+        let error = input_generator("forward 1\nswim 2").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!("day2 line 2 column 1: expected `{}`, found `swim`", VERBS)
+        );
+    }
+    #[test]
+    fn d2_script() {
+        let script = "\
+# dive in steps
+let step = 4
+macro descend {
+    down $step
+    forward 1  # keep moving
+}
+repeat 3 {
+    descend
+    let step = 2
+}
+repeat 0 {
+    up 100
+}
+forward $step
+";
+        let commands = expand_script(script).unwrap();
+        let expected =
+            input_generator("down 4\nforward 1\ndown 2\nforward 1\ndown 2\nforward 1\nforward 2")
+                .unwrap();
+        assert_eq!(commands, expected);
+        let error = |script| expand_script(script).unwrap_err().to_string();
+        assert_eq!(
+            error("repeat 2 {\n  forward $x\n}"),
+            "day2 line 2 column 11: expected `defined variable`, found `$x`"
+        );
+        assert_eq!(
+            error("let x = -1\nforward $x"),
+            "day2 line 2 column 9: expected `amount`, found `$x`"
+        );
+        assert_eq!(
+            error("macro a {\n  b\n}\nmacro b {\n  a\n}\na"),
+            "day2 line 5 column 3: expected `non-recursive macro`, found `a`"
+        );
+        assert_eq!(
+            error("repeat 2 {\n  forward 1"),
+            "day2 line 1 column 10: expected `matching }`, found `{`"
+        );
+        assert_eq!(
+            error("forward 1\n  swim"),
+            "day2 line 2 column 3: expected `command or macro`, found `swim`"
+        );
+    }
+    #[test]
+    fn d2_script_limit() {
+        let error = |script| expand_script(script).unwrap_err().to_string();
+        assert_eq!(
+            error(
+                "forward 1
+repeat 100000 {
+  repeat 100000 {
+    forward 1
+  }
+}"
+            ),
+            "day2 line 2 column 8: expected `expansion of at most 1000000 steps`, found `100000`"
+        );
+        assert_eq!(
+            error(
+                "macro a {
+  repeat 1000000000 {
+  }
+}
+forward 1
+a"
+            ),
+            "day2 line 6 column 1: expected `expansion of at most 1000000 steps`, found `a`"
+        );
+        assert_eq!(
+            expand_script(
+                "repeat 1000 {
+  repeat 499 {
+    forward 1
+  }
+}"
+            )
+            .unwrap()
+            .len(),
+            499_000
+        );
+    }
+    fn command() -> impl Strategy<Value = Command> {
+        prop_oneof![
+            (0..1000u32).prop_map(Command::Forward),
+            (0..1000u32).prop_map(Command::Down),
+            (0..1000u32).prop_map(Command::Up),
+        ]
+    }
+    proptest! {
+        #[test]
+        fn d2_navigations_match_folds(commands in prop::collection::vec(command(), 0..50)) {
+            let (mut horizontal, mut depth, mut aimed_depth, mut aim) = (0i64, 0i64, 0i64, 0i64);
+            for command in &commands {
+                match *command {
+                    Command::Forward(x) => {
+                        horizontal += x as i64;
+                        aimed_depth = (aimed_depth + aim * x as i64).max(0);
+                    }
+                    Command::Down(x) => {
+                        depth += x as i64;
+                        aim += x as i64;
+                    }
+                    Command::Up(x) => {
+                        depth = (depth - x as i64).max(0);
+                        aim -= x as i64;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            let direct = Submarine::new(Direct)
+                .with_surface_mode(SurfaceMode::Clamp)
+                .run(&commands)
+                .unwrap();
+            prop_assert_eq!((direct.horizontal, direct.depth), (horizontal, depth));
+            let aimed = Submarine::new(Aimed)
+                .with_surface_mode(SurfaceMode::Clamp)
+                .run(&commands)
+                .unwrap();
+            prop_assert_eq!((aimed.horizontal, aimed.depth, aimed.aim), (horizontal, aimed_depth, aim));
+        }
+        #[test]
+        fn d2_repeat_expands_body(commands in prop::collection::vec(command(), 0..10), n in 0..5usize) {
+            let body: Vec<String> = commands.iter().map(Command::to_string).collect();
+            let script = format!("repeat {} {{\n{}\n}}", n, body.join("\n"));
+            prop_assert_eq!(expand_script(&script).unwrap(), commands.repeat(n));
+        }
+    }
 }
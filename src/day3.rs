@@ -1,105 +1,839 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt,
+};
 
-#[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> Vec<String> {
-    input
-        .lines()
-        .map(|line| line.to_owned())
-        .collect::<Vec<_>>()
-}
-
-fn toto(input: &[String]) -> Vec<i16> {
-    input.into_iter().fold(Vec::new(), |mut acc, bits| {
-        if acc.is_empty() {
-            acc = bits
-                .chars()
-                .map(|b| if b == '0' { -1 } else { 1 })
-                .collect();
-        } else {
-            for (i, b) in bits.chars().enumerate() {
-                if b == '0' {
-                    acc[i] -= 1
-                } else {
-                    acc[i] += 1
+use crate::{
+    answer::Answer,
+    error::ParseError,
+    input::{self, Line},
+};
+
+/// A reading, or a value computed from readings like gamma, of `width` bits.
+/// Bit 0 is the least significant one, the last character of a line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reading {
+    width: usize,
+    /// Least significant word first.
+    words: Vec<u64>,
+}
+
+impl Reading {
+    fn zero(width: usize) -> Self {
+        Self {
+            width,
+            words: vec![0; width.div_ceil(64)],
+        }
+    }
+
+    fn set(&mut self, bit: usize) {
+        self.words[bit / 64] |= 1 << (bit % 64);
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn bit(&self, bit: usize) -> bool {
+        self.words[bit / 64] >> (bit % 64) & 1 == 1
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// `None` if a bit above the 128th is set.
+    pub fn to_u128(&self) -> Option<u128> {
+        match self.words[..] {
+            [] => Some(0),
+            [low] => Some(low as u128),
+            [low, high, ref rest @ ..] if rest.iter().all(|w| *w == 0) => {
+                Some((high as u128) << 64 | low as u128)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Reading {
+    /// Same format as the input.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for bit in (0..self.width).rev() {
+            f.write_str(if self.bit(bit) { "1" } else { "0" })?;
+        }
+        Ok(())
+    }
+}
+
+/// Readings of the same width, packed in `width.div_ceil(64)` words each.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticReport {
+    width: usize,
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl DiagnosticReport {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            stride: width.div_ceil(64),
+            words: vec![],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len().checked_div(self.stride).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Adds a reading given most significant bit first, like in the input.
+    /// Panics unless there are exactly `width` bits.
+    pub fn push(&mut self, bits: impl IntoIterator<Item = bool>) {
+        let start = self.words.len();
+        self.words.resize(start + self.stride, 0);
+        let mut count = 0;
+        for (i, one) in bits.into_iter().enumerate() {
+            assert!(i < self.width, "reading wider than {} bits", self.width);
+            if one {
+                let bit = self.width - 1 - i;
+                self.words[start + bit / 64] |= 1 << (bit % 64);
+            }
+            count += 1;
+        }
+        assert_eq!(
+            count, self.width,
+            "reading narrower than {} bits",
+            self.width
+        );
+    }
+
+    fn words_of(&self, reading: usize) -> &[u64] {
+        &self.words[reading * self.stride..(reading + 1) * self.stride]
+    }
+
+    pub fn bit(&self, reading: usize, bit: usize) -> bool {
+        self.words_of(reading)[bit / 64] >> (bit % 64) & 1 == 1
+    }
+
+    pub fn reading(&self, reading: usize) -> Reading {
+        Reading {
+            width: self.width,
+            words: self.words_of(reading).to_vec(),
+        }
+    }
+
+    pub fn readings(&self) -> impl Iterator<Item = Reading> + '_ {
+        (0..self.len()).map(|i| self.reading(i))
+    }
+
+    /// Number of readings with `bit` set.
+    pub fn ones(&self, bit: usize) -> usize {
+        let (word, shift) = (bit / 64, bit % 64);
+        self.words
+            .iter()
+            .skip(word)
+            .step_by(self.stride.max(1))
+            .filter(|w| *w >> shift & 1 == 1)
+            .count()
+    }
+
+    /// `ones` of every bit, in one pass over the readings.
+    pub fn column_ones(&self) -> Vec<usize> {
+        let mut ones = vec![0; self.width];
+        if self.stride == 0 {
+            return ones;
+        }
+        for reading in self.words.chunks(self.stride) {
+            for (i, word) in reading.iter().enumerate() {
+                let mut word = *word;
+                while word != 0 {
+                    ones[i * 64 + word.trailing_zeros() as usize] += 1;
+                    word &= word - 1;
                 }
             }
         }
-        acc
-    })
+        ones
+    }
+
+    /// For each bit, the value `criteria` keeps among all the readings.
+    pub fn common_bits(&self, criteria: &BitCriteria) -> Reading {
+        let mut common = Reading::zero(self.width);
+        for (bit, ones) in self.column_ones().into_iter().enumerate() {
+            if criteria.keep(ones, self.len()) {
+                common.set(bit);
+            }
+        }
+        common
+    }
+
+    /// Frequencies of `0` and `1` in each column, first character first,
+    /// with both in the alphabet even when a column lacks one.
+    pub fn frequencies(&self) -> ColumnFrequencies {
+        let columns = self
+            .column_ones()
+            .into_iter()
+            .rev()
+            .map(|ones| {
+                [('0', self.len() - ones), ('1', ones)]
+                    .into_iter()
+                    .filter(|(_, count)| *count > 0)
+                    .collect()
+            })
+            .collect();
+        ColumnFrequencies {
+            alphabet: BTreeSet::from(['0', '1']),
+            columns,
+        }
+    }
+
+    /// Most common bits, `1` on ties.
+    pub fn gamma(&self) -> Reading {
+        self.common_bits(&BitCriteria::OXYGEN)
+    }
+
+    /// Least common bits, `0` on ties.
+    pub fn epsilon(&self) -> Reading {
+        self.common_bits(&BitCriteria::CO2)
+    }
+
+    /// Narrows the readings down bit by bit, keeping those with the bit `criteria` chooses,
+    /// until one is left. Bits all the candidates share are skipped, so only duplicates
+    /// (or an empty report) leave no unique reading.
+    pub fn rating(&self, criteria: &BitCriteria) -> Result<Reading, DiagnosticError> {
+        self.explain(criteria).rating
+    }
+
+    /// `rating`, with what happened at each bit.
+    pub fn explain(&self, criteria: &BitCriteria) -> RatingTrace {
+        let mut candidates: Vec<usize> = (0..self.len()).collect();
+        let mut steps = vec![];
+        for bit in criteria.order.bits(self.width) {
+            if candidates.len() <= 1 {
+                break;
+            }
+            let total = candidates.len();
+            let ones = candidates.iter().filter(|i| self.bit(**i, bit)).count();
+            let kept = if ones == 0 || ones == total {
+                None
+            } else {
+                let keep = criteria.keep(ones, total);
+                candidates.retain(|i| self.bit(*i, bit) == keep);
+                Some(keep)
+            };
+            steps.push(FilterStep {
+                bit,
+                ones,
+                zeros: total - ones,
+                kept,
+                left: candidates.len(),
+            });
+        }
+        let rating = match candidates[..] {
+            [reading] => Ok(self.reading(reading)),
+            _ => Err(DiagnosticError::NoUniqueReading {
+                criteria: *criteria,
+                left: candidates.len(),
+            }),
+        };
+        RatingTrace {
+            criteria: *criteria,
+            steps,
+            rating,
+        }
+    }
+
+    pub fn oxygen(&self) -> Result<Reading, DiagnosticError> {
+        self.rating(&BitCriteria::OXYGEN)
+    }
+
+    pub fn co2(&self) -> Result<Reading, DiagnosticError> {
+        self.rating(&BitCriteria::CO2)
+    }
 }
 
-#[aoc(day3, part1)]
-pub fn solve_part1(input: &[String]) -> u64 {
-    let tata = toto(input);
-    let mut gamma = 0;
-    let mut epsilon = 0;
-    for (i, count) in tata.iter().rev().enumerate() {
-        if *count < 0 {
-            epsilon += 1 << i;
+/// What `DiagnosticReport::explain` did at a bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterStep {
+    pub bit: usize,
+    /// Among the candidates left before this bit.
+    pub ones: usize,
+    pub zeros: usize,
+    /// `None` when all the candidates have the same bit and were kept.
+    pub kept: Option<bool>,
+    pub left: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RatingTrace {
+    pub criteria: BitCriteria,
+    pub steps: Vec<FilterStep>,
+    pub rating: Result<Reading, DiagnosticError>,
+}
+
+impl fmt::Display for RatingTrace {
+    /// One line per bit, then the rating in binary and decimal.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.criteria {
+            BitCriteria::OXYGEN => "oxygen generator".to_owned(),
+            BitCriteria::CO2 => "CO2 scrubber".to_owned(),
+            criteria => format!("{:?}", criteria),
+        };
+        writeln!(f, "{} rating", name)?;
+        for step in &self.steps {
+            write!(
+                f,
+                "bit {}: {} ones, {} zeros, ",
+                step.bit, step.ones, step.zeros
+            )?;
+            match step.kept {
+                Some(bit) => write!(f, "keep {}", bit as u8)?,
+                None => write!(f, "all alike")?,
+            }
+            writeln!(f, ", {} left", step.left)?;
+        }
+        match &self.rating {
+            Ok(reading) => match reading.to_u128() {
+                Some(value) => writeln!(f, "= {} ({})", reading, value),
+                None => writeln!(f, "= {}", reading),
+            },
+            Err(e) => writeln!(f, "error: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    MostCommon,
+    LeastCommon,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// From the first character of a line, as in the puzzle.
+    MsbFirst,
+    LsbFirst,
+}
+
+impl BitOrder {
+    fn bits(self, width: usize) -> Box<dyn Iterator<Item = usize>> {
+        match self {
+            BitOrder::MsbFirst => Box::new((0..width).rev()),
+            BitOrder::LsbFirst => Box::new(0..width),
+        }
+    }
+}
+
+/// Which bit value to keep when filtering readings, or when computing gamma and epsilon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitCriteria {
+    pub keep: Keep,
+    /// Kept when there are as many `0`s as `1`s.
+    pub tie: bool,
+    pub order: BitOrder,
+}
+
+impl BitCriteria {
+    pub const OXYGEN: Self = Self {
+        keep: Keep::MostCommon,
+        tie: true,
+        order: BitOrder::MsbFirst,
+    };
+
+    pub const CO2: Self = Self {
+        keep: Keep::LeastCommon,
+        tie: false,
+        order: BitOrder::MsbFirst,
+    };
+
+    /// The bit value to keep, given how many of `total` readings have it set.
+    /// A value no reading has counts as the least common one.
+    pub fn keep(&self, ones: usize, total: usize) -> bool {
+        let zeros = total - ones;
+        if ones == zeros {
+            self.tie
         } else {
-            gamma += 1 << i;
+            (ones > zeros) == (self.keep == Keep::MostCommon)
         }
     }
-    gamma * epsilon
 }
 
-fn filter(input: &[String], index: usize, positive: bool) -> u64 {
-    let tata = toto(input);
-    let a = if tata[index] >= 0 && positive {
-        input
-            .into_iter()
-            .cloned()
-            .filter(|x| (**x).chars().nth(index) == Some('1'))
-            .collect::<Vec<_>>()
-    } else if tata[index] < 0 && positive {
-        input
-            .into_iter()
-            .cloned()
-            .filter(|x| (**x).chars().nth(index) == Some('0'))
-            .collect::<Vec<_>>()
-    } else if tata[index] < 0 && !positive {
-        input
-            .into_iter()
-            .cloned()
-            .filter(|x| (**x).chars().nth(index) == Some('1'))
-            .collect::<Vec<_>>()
-    } else if tata[index] >= 0 && !positive {
-        input
-            .into_iter()
-            .cloned()
-            .filter(|x| (**x).chars().nth(index) == Some('0'))
-            .collect::<Vec<_>>()
-    } else {
-        unreachable!();
+/// How often each symbol appears in each column of rows of text, whatever the alphabet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnFrequencies {
+    alphabet: BTreeSet<char>,
+    columns: Vec<BTreeMap<char, usize>>,
+}
+
+impl ColumnFrequencies {
+    /// `alphabet` lists the symbols to consider even when absent from a column,
+    /// other symbols are added as they are found.
+    pub fn new(alphabet: impl IntoIterator<Item = char>) -> Self {
+        Self {
+            alphabet: alphabet.into_iter().collect(),
+            columns: vec![],
+        }
+    }
+
+    /// Counts a row, rows shorter than others only count in their first columns.
+    pub fn add(&mut self, row: impl IntoIterator<Item = char>) {
+        for (i, symbol) in row.into_iter().enumerate() {
+            if i == self.columns.len() {
+                self.columns.push(BTreeMap::new());
+            }
+            *self.columns[i].entry(symbol).or_default() += 1;
+            self.alphabet.insert(symbol);
+        }
+    }
+
+    pub fn alphabet(&self) -> &BTreeSet<char> {
+        &self.alphabet
+    }
+
+    /// Frequency table of each column, without the absent symbols.
+    pub fn columns(&self) -> &[BTreeMap<char, usize>] {
+        &self.columns
+    }
+
+    pub fn count(&self, column: usize, symbol: char) -> usize {
+        self.columns[column].get(&symbol).copied().unwrap_or(0)
+    }
+
+    /// Most common symbol of each column, the greatest one on ties.
+    pub fn mode(&self) -> String {
+        (0..self.columns.len())
+            .map(|i| {
+                let counts = self.alphabet.iter().map(|s| (self.count(i, *s), *s));
+                counts.max().unwrap().1
+            })
+            .collect()
+    }
+
+    /// Least common symbol of the alphabet in each column, the smallest one on ties.
+    pub fn anti_mode(&self) -> String {
+        (0..self.columns.len())
+            .map(|i| {
+                let counts = self.alphabet.iter().map(|s| (self.count(i, *s), *s));
+                counts.min().unwrap().1
+            })
+            .collect()
+    }
+}
+
+impl<'a> FromIterator<&'a str> for ColumnFrequencies {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(rows: I) -> Self {
+        let mut frequencies = Self::default();
+        for row in rows {
+            frequencies.add(row.chars());
+        }
+        frequencies
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticError {
+    /// The report has no reading.
+    Empty,
+    /// The filtering ended with no reading, or with duplicates.
+    NoUniqueReading { criteria: BitCriteria, left: usize },
+    /// The product of two ratings does not fit in 128 bits.
+    Overflow,
+}
+
+impl fmt::Display for DiagnosticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticError::Empty => write!(f, "no reading in the report"),
+            DiagnosticError::NoUniqueReading { criteria, left } => write!(
+                f,
+                "no unique reading with {:?}, {} left after the last bit",
+                criteria, left
+            ),
+            DiagnosticError::Overflow => write!(f, "rating product overflows 128 bits"),
+        }
+    }
+}
+
+impl Error for DiagnosticError {}
+
+fn product(a: &Reading, b: &Reading) -> Result<Answer, DiagnosticError> {
+    a.to_u128()
+        .zip(b.to_u128())
+        .and_then(|(a, b)| a.checked_mul(b))
+        .map(Answer::from)
+        .ok_or(DiagnosticError::Overflow)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidReadingKind {
+    Empty,
+    /// Neither `0` nor `1`.
+    Symbol(char),
+    Width {
+        expected: usize,
+        found: usize,
+    },
+    /// No line of the input is a reading, reported on line 1.
+    NoReading,
+}
+
+/// A line of a report that is not a reading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidReading {
+    /// 1-based, like `column`.
+    pub line: usize,
+    pub column: usize,
+    /// The line without its surrounding whitespace.
+    pub text: String,
+    pub kind: InvalidReadingKind,
+}
+
+impl fmt::Display for InvalidReading {
+    /// Same as the `ParseError` the generator returns.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        ParseError::from(self.clone()).fmt(f)
+    }
+}
+
+impl Error for InvalidReading {}
+
+impl From<InvalidReading> for ParseError {
+    fn from(invalid: InvalidReading) -> Self {
+        let (token, expected) = match invalid.kind {
+            InvalidReadingKind::Empty => (String::new(), "0|1"),
+            InvalidReadingKind::Symbol(c) => (c.to_string(), "0|1"),
+            InvalidReadingKind::Width { .. } => (invalid.text, "same width as the first reading"),
+            InvalidReadingKind::NoReading => (String::new(), "reading"),
+        };
+        ParseError {
+            day: 3,
+            line: invalid.line,
+            column: invalid.column,
+            token,
+            expected,
+        }
+    }
+}
+
+/// The bits of `line`, if it is a reading of `width` bits (any if `None`).
+fn reading_bits<'a>(line: &Line<'a>, width: Option<usize>) -> Result<&'a str, InvalidReading> {
+    let bits = line.text.trim_start();
+    let start = line.text.len() - bits.len() + 1;
+    let invalid = |column, kind| InvalidReading {
+        line: line.number,
+        column,
+        text: bits.to_owned(),
+        kind,
     };
-    if a.len() > 1 {
-        filter(a.as_slice(), index + 1, positive)
-    } else {
-        let mut ret = 0;
-        for (i, b) in a[0].chars().rev().enumerate() {
-            if b == '1' {
-                ret += 1 << i;
+    if bits.is_empty() {
+        return Err(invalid(start, InvalidReadingKind::Empty));
+    }
+    if let Some((i, c)) = bits.char_indices().find(|(_, c)| *c != '0' && *c != '1') {
+        return Err(invalid(start + i, InvalidReadingKind::Symbol(c)));
+    }
+    match width {
+        Some(expected) if expected != bits.len() => Err(invalid(
+            start,
+            InvalidReadingKind::Width {
+                expected,
+                found: bits.len(),
+            },
+        )),
+        _ => Ok(bits),
+    }
+}
+
+impl DiagnosticReport {
+    /// Readings of `input`, calling `on_invalid` on the lines that are not,
+    /// the width being set by the first valid one.
+    fn parse_with(
+        input: &str,
+        mut on_invalid: impl FnMut(InvalidReading) -> Result<(), InvalidReading>,
+    ) -> Result<Self, InvalidReading> {
+        let mut report: Option<DiagnosticReport> = None;
+        for line in input::lines(3, input) {
+            match reading_bits(&line, report.as_ref().map(Self::width)) {
+                Ok(bits) => report
+                    .get_or_insert_with(|| Self::new(bits.len()))
+                    .push(bits.bytes().map(|b| b == b'1')),
+                Err(invalid) => on_invalid(invalid)?,
             }
         }
-        ret
+        report.ok_or(InvalidReading {
+            line: 1,
+            column: 1,
+            text: String::new(),
+            kind: InvalidReadingKind::NoReading,
+        })
+    }
+
+    /// Every line must be made of `0`s and `1`s, as many as on the first line,
+    /// and there must be at least one line.
+    pub fn parse(input: &str) -> Result<Self, InvalidReading> {
+        Self::parse_with(input, Err)
+    }
+
+    /// Skips the lines `parse` would fail on, returning them along with the report.
+    /// Fails only when no line is a reading.
+    pub fn parse_lenient(input: &str) -> Result<(Self, Vec<InvalidReading>), InvalidReading> {
+        let mut dropped = vec![];
+        let report = Self::parse_with(input, |invalid| {
+            dropped.push(invalid);
+            Ok(())
+        })?;
+        Ok((report, dropped))
+    }
+}
+
+#[aoc_generator(day3)]
+pub fn input_generator(input: &str) -> Result<DiagnosticReport, ParseError> {
+    Ok(DiagnosticReport::parse(input)?)
+}
+
+/// Reading from the `0`s and `1`s of `bits`, most significant first.
+fn binary(bits: &str) -> Reading {
+    let mut reading = Reading::zero(bits.len());
+    for (bit, c) in bits.bytes().rev().enumerate() {
+        if c == b'1' {
+            reading.set(bit);
+        }
     }
+    reading
+}
+
+#[aoc(day3, part1)]
+pub fn solve_part1(input: &DiagnosticReport) -> Result<Answer, DiagnosticError> {
+    if input.is_empty() {
+        return Err(DiagnosticError::Empty);
+    }
+    let frequencies = input.frequencies();
+    product(
+        &binary(&frequencies.mode()),
+        &binary(&frequencies.anti_mode()),
+    )
 }
 
 #[aoc(day3, part2)]
-pub fn solve_part2(input: &[String]) -> u64 {
-    let oxygen = dbg!(filter(input.clone(), 0, true));
-    let co2 = dbg!(filter(input, 0, false));
-    oxygen * co2
+pub fn solve_part2(input: &DiagnosticReport) -> Result<Answer, DiagnosticError> {
+    if input.is_empty() {
+        return Err(DiagnosticError::Empty);
+    }
+    product(&input.oxygen()?, &input.co2()?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    const EXAMPLE: &str =
+        "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010";
     #[test]
     fn d3_part2() {
-        let input = input_generator(
-            "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010",
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part2(&input), Ok(230.into()));
+    }
+    #[test]
+    fn d3_packed() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!((input.len(), input.width()), (12, 5));
+        assert_eq!(input.column_ones(), vec![5, 7, 8, 5, 7]);
+        assert_eq!(input.ones(4), 7);
+        assert_eq!(input.gamma().to_string(), "10110");
+        assert_eq!(input.oxygen().unwrap().to_string(), "10111");
+
+        let wide = format!("{}\n{}", "10".repeat(50), "0".repeat(100));
+        let input = input_generator(&wide).unwrap();
+        assert_eq!(input.column_ones()[99], 1);
+        assert_eq!(input.reading(0).to_string(), "10".repeat(50));
+        assert_eq!(input.oxygen().unwrap().words().len(), 2);
+        assert_eq!(solve_part2(&input), Ok(0.into()));
+        assert_eq!(solve_part1(&input), Err(DiagnosticError::Overflow));
+    }
+    #[test]
+    fn d3_empty() {
+        assert_eq!(
+            input_generator("").unwrap_err().to_string(),
+            "day3 line 1 column 1: expected `reading`, found ``"
         );
-        assert_eq!(solve_part2(input.as_slice()), 230);
+        let input = DiagnosticReport::new(0);
+        assert_eq!(input.column_ones(), vec![]);
+        assert_eq!(solve_part1(&input), Err(DiagnosticError::Empty));
+        assert_eq!(solve_part2(&input), Err(DiagnosticError::Empty));
+    }
+    #[test]
+    fn d3_frequencies() {
+        let input = input_generator(EXAMPLE).unwrap();
+        let frequencies = input.frequencies();
+        assert_eq!(frequencies.mode(), input.gamma().to_string());
+        assert_eq!(frequencies.anti_mode(), input.epsilon().to_string());
+        assert_eq!(frequencies, EXAMPLE.lines().collect::<ColumnFrequencies>());
+
+        let uniform = input_generator("11\n10").unwrap().frequencies();
+        assert_eq!(uniform.columns()[0], BTreeMap::from([('1', 2)]));
+        assert_eq!(
+            uniform,
+            ["11", "10"].into_iter().collect::<ColumnFrequencies>()
+        );
+        assert_eq!(
+            (uniform.mode(), uniform.anti_mode()),
+            ("11".into(), "00".into())
+        );
+
+        let dna: ColumnFrequencies = ["ACGT", "ACGA", "TCGA"].into_iter().collect();
+        assert_eq!(dna.columns()[0], BTreeMap::from([('A', 2), ('T', 1)]));
+        assert_eq!(dna.mode(), "ACGA");
+        assert_eq!(dna.anti_mode(), "CAAC");
+
+        let mut hex = ColumnFrequencies::new("0123456789abcdef".chars());
+        for row in ["ff0", "f1", "a10"] {
+            hex.add(row.chars());
+        }
+        assert_eq!(hex.count(2, '0'), 2);
+        assert_eq!(hex.mode(), "f10");
+        assert_eq!(hex.anti_mode(), "001");
+    }
+    #[test]
+    fn d3_validation() {
+        let input = "10110\n\n  1x110\n101\n00111\n";
+        assert_eq!(
+            DiagnosticReport::parse(input).unwrap_err().kind,
+            InvalidReadingKind::Empty
+        );
+        let (report, dropped) = DiagnosticReport::parse_lenient(input).unwrap();
+        assert_eq!((report.len(), dropped.len()), (2, 3));
+        assert_eq!(
+            dropped[1].to_string(),
+            "day3 line 3 column 4: expected `0|1`, found `x`"
+        );
+        assert_eq!(
+            dropped[1].to_string(),
+            input_generator("10110\n00111\n  1x110")
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            DiagnosticReport::parse_lenient("x\n\n2").unwrap_err().kind,
+            InvalidReadingKind::NoReading
+        );
+        assert_eq!(
+            dropped[2].kind,
+            InvalidReadingKind::Width {
+                expected: 5,
+                found: 3
+            }
+        );
+        assert_eq!(
+            input_generator("10110\n101").unwrap_err().to_string(),
+            "day3 line 2 column 1: expected `same width as the first reading`, found `101`"
+        );
+        assert_eq!(
+            input_generator(" 10110\n 10x10").unwrap_err().to_string(),
+            "day3 line 2 column 4: expected `0|1`, found `x`"
+        );
+    }
+    #[test]
+    fn d3_explain() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(
+            input.explain(&BitCriteria::OXYGEN).to_string(),
+            "oxygen generator rating
+bit 4: 7 ones, 5 zeros, keep 1, 7 left
+bit 3: 3 ones, 4 zeros, keep 0, 4 left
+bit 2: 3 ones, 1 zeros, keep 1, 3 left
+bit 1: 2 ones, 1 zeros, keep 1, 2 left
+bit 0: 1 ones, 1 zeros, keep 1, 1 left
+= 10111 (23)
+"
+        );
+        let trace = input.explain(&BitCriteria::CO2);
+        assert_eq!(trace.steps.len(), 3);
+        assert_eq!(trace.rating.unwrap().to_u128(), Some(10));
+        let input = input_generator("10110\n10111").unwrap();
+        assert_eq!(
+            input.explain(&BitCriteria::CO2).to_string(),
+            "CO2 scrubber rating
+bit 4: 2 ones, 0 zeros, all alike, 2 left
+bit 3: 0 ones, 2 zeros, all alike, 2 left
+bit 2: 2 ones, 0 zeros, all alike, 2 left
+bit 1: 2 ones, 0 zeros, all alike, 2 left
+bit 0: 1 ones, 1 zeros, keep 0, 1 left
+= 10110 (22)
+"
+        );
+    }
+    #[test]
+    fn d3_criteria() {
+        let input = input_generator(EXAMPLE).unwrap();
+        let lsb = BitCriteria {
+            order: BitOrder::LsbFirst,
+            ..BitCriteria::OXYGEN
+        };
+        assert_eq!(input.rating(&lsb).unwrap().to_string(), "11110");
+        let fewest_ones = BitCriteria {
+            tie: false,
+            ..BitCriteria::OXYGEN
+        };
+        assert_eq!(input.rating(&fewest_ones).unwrap().to_string(), "10110");
+
+        let input = input_generator("10110\n10110\n00111").unwrap();
+        assert_eq!(
+            input.oxygen(),
+            Err(DiagnosticError::NoUniqueReading {
+                criteria: BitCriteria::OXYGEN,
+                left: 2
+            })
+        );
+        let input = input_generator("10110\n10111").unwrap();
+        assert_eq!(input.co2().unwrap().to_string(), "10110");
+        assert_eq!(
+            DiagnosticReport::new(5).co2().unwrap_err().to_string(),
+            "no unique reading with BitCriteria { keep: LeastCommon, tie: false, order: MsbFirst }, 0 left after the last bit"
+        );
+
+        let wide = format!(
+            "1{}\n0{}\n0{}",
+            "0".repeat(255),
+            "1".repeat(255),
+            "0".repeat(255)
+        );
+        let input = input_generator(&wide).unwrap();
+        assert_eq!(input.oxygen().unwrap(), input.reading(1));
+        assert_eq!(input.co2().unwrap(), input.reading(0));
+        assert_eq!(input.gamma().to_string(), "0".repeat(256));
+    }
+    proptest! {
+        #[test]
+        fn d3_gamma_epsilon_match_column_counts(
+            report in (1..12usize).prop_flat_map(|width| {
+                prop::collection::vec(prop::collection::vec(any::<bool>(), width), 1..50)
+            })
+        ) {
+            let width = report[0].len();
+            let (mut gamma, mut epsilon) = (0u128, 0u128);
+            for column in 0..width {
+                let ones = report.iter().filter(|bits| bits[column]).count();
+                gamma <<= 1;
+                epsilon <<= 1;
+                if ones * 2 >= report.len() {
+                    gamma += 1;
+                } else {
+                    epsilon += 1;
+                }
+            }
+            let mut packed = DiagnosticReport::new(width);
+            for bits in &report {
+                packed.push(bits.iter().copied());
+            }
+            prop_assert_eq!(solve_part1(&packed), Ok((gamma * epsilon).into()));
+        }
     }
 }
@@ -1,131 +1,504 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::{collections::HashMap, error::Error, fmt};
 
-#[derive(Clone)]
-struct Number {
-    value: u8,
-    marked: bool,
+use crate::{answer::Answer, error::ParseError, input};
+
+/// A rectangular card, its cells stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+    columns: usize,
+    numbers: Vec<u32>,
+    marked: Vec<bool>,
 }
 
-impl From<u8> for Number {
-    fn from(v: u8) -> Self {
+impl Board {
+    /// Panics if the rows are empty or do not have the same length.
+    pub fn from_rows(rows: Vec<Vec<u32>>) -> Self {
+        let columns = rows.first().map_or(0, Vec::len);
+        assert!(columns > 0, "empty board");
+        assert!(
+            rows.iter().all(|row| row.len() == columns),
+            "rows of different lengths"
+        );
+        let numbers: Vec<u32> = rows.into_iter().flatten().collect();
         Self {
-            value: v,
-            marked: false,
+            columns,
+            marked: vec![false; numbers.len()],
+            numbers,
         }
     }
-}
-#[derive(Clone)]
-struct Line(Vec<Number>);
 
-impl Line {
-    fn is_a_winner(&self) -> bool {
-        self.0.iter().all(|n| n.marked)
+    pub fn rows(&self) -> usize {
+        self.numbers.len() / self.columns
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
     }
 
-    fn sum_unmarked(&self) -> u32 {
-        self.0
+    pub fn number(&self, row: usize, column: usize) -> u32 {
+        self.numbers[row * self.columns + column]
+    }
+
+    pub fn is_marked(&self, row: usize, column: usize) -> bool {
+        self.marked[row * self.columns + column]
+    }
+
+    pub fn unmarked(&self) -> impl Iterator<Item = u32> + '_ {
+        self.numbers
             .iter()
-            .map(|n| if n.marked { 0 } else { n.value as u32 })
-            .sum()
+            .zip(&self.marked)
+            .filter(|(_, marked)| !**marked)
+            .map(|(number, _)| *number)
     }
 }
 
-#[derive(Default, Clone)]
-pub struct Board {
-    lines: Vec<Line>,
-    complete: bool,
+/// Cells a board must have marked to win.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WinPattern {
+    /// Any row.
+    Rows,
+    /// Any column.
+    Columns,
+    /// Any of the two diagonals, only on square boards.
+    Diagonals,
+    FourCorners,
+    FullCard,
+    /// The `true` cells, the mask being laid over the top left corner of the board.
+    /// Never wins on boards smaller than the mask, and must have at least one `true` cell.
+    Mask(Vec<Vec<bool>>),
 }
 
-impl Board {
-    fn get_score(&self, draw: u8) -> u32 {
-        draw as u32
-            * self
-                .lines
-                .iter()
-                .map(|line| line.sum_unmarked())
-                .sum::<u32>()
+impl WinPattern {
+    /// Cell indices of every winning line of the pattern on `board`.
+    fn lines(&self, board: &Board) -> Vec<Vec<usize>> {
+        let (rows, columns) = (board.rows(), board.columns());
+        let cell = |row: usize, column: usize| row * columns + column;
+        match self {
+            WinPattern::Rows => (0..rows)
+                .map(|r| (0..columns).map(|c| cell(r, c)).collect())
+                .collect(),
+            WinPattern::Columns => (0..columns)
+                .map(|c| (0..rows).map(|r| cell(r, c)).collect())
+                .collect(),
+            WinPattern::Diagonals if rows == columns => vec![
+                (0..rows).map(|i| cell(i, i)).collect(),
+                (0..rows).map(|i| cell(i, columns - 1 - i)).collect(),
+            ],
+            WinPattern::Diagonals => vec![],
+            WinPattern::FourCorners => {
+                let mut corners = vec![
+                    cell(0, 0),
+                    cell(0, columns - 1),
+                    cell(rows - 1, 0),
+                    cell(rows - 1, columns - 1),
+                ];
+                corners.sort_unstable();
+                corners.dedup();
+                vec![corners]
+            }
+            WinPattern::FullCard => vec![(0..rows * columns).collect()],
+            WinPattern::Mask(mask) => {
+                let mut cells = vec![];
+                for (r, mask_row) in mask.iter().enumerate() {
+                    for (c, _) in mask_row.iter().enumerate().filter(|(_, set)| **set) {
+                        if r >= rows || c >= columns {
+                            return vec![];
+                        }
+                        cells.push(cell(r, c));
+                    }
+                }
+                vec![cells]
+            }
+        }
+    }
+}
+
+/// Score of a board winning on `draw`. Closures taking the board and the draw can be used.
+pub trait Scoring {
+    fn score(&self, board: &Board, draw: u32) -> u64;
+}
+
+impl<F: Fn(&Board, u32) -> u64> Scoring for F {
+    fn score(&self, board: &Board, draw: u32) -> u64 {
+        self(board, draw)
+    }
+}
+
+/// The puzzle's: sum of the unmarked numbers times the draw.
+pub struct UnmarkedSum;
+
+impl Scoring for UnmarkedSum {
+    fn score(&self, board: &Board, draw: u32) -> u64 {
+        board.unmarked().map(u64::from).sum::<u64>() * draw as u64
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Win {
+    /// Index of the board in the game.
+    pub board: usize,
+    pub draw: u32,
+    pub score: u64,
+}
+
+/// Winning lines of a board with how many of their cells are marked.
+struct Lines {
+    sizes: Vec<usize>,
+    marked: Vec<usize>,
+    /// Lines each cell belongs to.
+    of_cell: Vec<Vec<usize>>,
+}
 
-    fn check_columns(&mut self, draw: u8) -> Option<u32> {
-        for (i, n) in self.lines[0].0.iter().enumerate() {
-            if n.marked && self.lines[1..].into_iter().all(|l| l.0[i].marked) {
-                self.complete = true;
-                return Some(self.get_score(draw));
+impl Lines {
+    fn new(board: &Board, patterns: &[WinPattern]) -> Self {
+        let lines: Vec<Vec<usize>> = patterns.iter().flat_map(|p| p.lines(board)).collect();
+        let mut of_cell = vec![vec![]; board.numbers.len()];
+        for (i, line) in lines.iter().enumerate() {
+            for cell in line {
+                of_cell[*cell].push(i);
             }
         }
-        None
+        Self {
+            sizes: lines.iter().map(Vec::len).collect(),
+            marked: vec![0; lines.len()],
+            of_cell,
+        }
     }
 
-    fn check_draw(&mut self, draw: u8) -> Option<u32> {
-        for l in self.lines.iter_mut() {
-            if let Some(mut number) = l.0.iter_mut().find(|x| x.value == draw) {
-                number.marked = true;
+    /// Counts `cell` as marked, returning whether it completed a line.
+    fn mark(&mut self, cell: usize) -> bool {
+        let mut complete = false;
+        for line in &self.of_cell[cell] {
+            self.marked[*line] += 1;
+            complete |= self.marked[*line] == self.sizes[*line];
+        }
+        complete
+    }
+}
+
+/// Boards playing until they win, each board winning at most once.
+/// A draw only looks at the cells with the drawn number.
+pub struct BingoGame<S> {
+    boards: Vec<Board>,
+    lines: Vec<Lines>,
+    /// `(board, row, column)` of each number, in board order then row-major order.
+    index: HashMap<u32, Vec<(usize, usize, usize)>>,
+    won: Vec<bool>,
+    winners: usize,
+    scoring: S,
+}
+
+impl<S: Scoring> BingoGame<S> {
+    /// Panics if a `Mask` pattern has no `true` cell, it would never win.
+    pub fn new(boards: Vec<Board>, patterns: &[WinPattern], scoring: S) -> Self {
+        assert!(
+            !patterns.iter().any(
+                |p| matches!(p, WinPattern::Mask(mask) if !mask.iter().flatten().any(|set| *set))
+            ),
+            "mask without any cell"
+        );
+        let lines = boards.iter().map(|b| Lines::new(b, patterns)).collect();
+        let mut index: HashMap<u32, Vec<(usize, usize, usize)>> = HashMap::new();
+        for (i, board) in boards.iter().enumerate() {
+            for (cell, number) in board.numbers.iter().enumerate() {
+                let position = (i, cell / board.columns, cell % board.columns);
+                index.entry(*number).or_default().push(position);
             }
-            if l.is_a_winner() {
-                self.complete = true;
-                return Some(self.get_score(draw));
+        }
+        Self {
+            won: vec![false; boards.len()],
+            winners: 0,
+            boards,
+            lines,
+            index,
+            scoring,
+        }
+    }
+
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+
+    pub fn has_won(&self, board: usize) -> bool {
+        self.won[board]
+    }
+
+    /// `(board, row, column)` of every cell with `number`.
+    pub fn find(&self, number: u32) -> &[(usize, usize, usize)] {
+        self.index.get(&number).map_or(&[], Vec::as_slice)
+    }
+
+    /// Boards winning with this draw, in board order.
+    pub fn draw(&mut self, number: u32) -> Vec<Win> {
+        let mut wins: Vec<Win> = vec![];
+        for (i, row, column) in self.index.get(&number).into_iter().flatten() {
+            let board = &mut self.boards[*i];
+            let cell = row * board.columns + column;
+            if self.won[*i] || board.marked[cell] {
+                continue;
+            }
+            board.marked[cell] = true;
+            if self.lines[*i].mark(cell) && wins.last().map(|w| w.board) != Some(*i) {
+                wins.push(Win {
+                    board: *i,
+                    draw: number,
+                    score: 0,
+                });
+            }
+        }
+        // scored once all the cells are marked, a board may have the number twice
+        for win in &mut wins {
+            self.won[win.board] = true;
+            win.score = self.scoring.score(&self.boards[win.board], number);
+        }
+        self.winners += wins.len();
+        wins
+    }
+
+    /// Every win in order, stopping once all the boards have won.
+    pub fn play(&mut self, draws: &[u32]) -> Vec<Win> {
+        let mut wins = vec![];
+        for draw in draws {
+            if self.winners == self.boards.len() {
+                break;
             }
+            wins.extend(self.draw(*draw));
         }
-        self.check_columns(draw)
+        wins
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoWinner;
+
+impl fmt::Display for NoWinner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no board wins")
+    }
+}
+
+impl Error for NoWinner {}
+
+#[derive(Debug, Clone)]
+pub struct Bingo {
+    pub draws: Vec<u32>,
+    pub boards: Vec<Board>,
+}
+
 #[aoc_generator(day4)]
-pub fn input_generator(input: &str) -> (Vec<u8>, Vec<Board>) {
-    let mut lines = input.lines().into_iter();
-    let draws: Vec<u8> = lines
-        .next()
-        .unwrap()
-        .trim()
-        .split(",")
-        .map(|x| x.parse().unwrap())
-        .collect();
-
-    let mut boards: Vec<Board> = vec![];
-    for line in lines {
-        if line.trim().is_empty() {
-            boards.push(Board::default());
-        } else {
-            let board_line = Line(
-                line.trim()
-                    .split(" ")
-                    .filter(|x| !x.is_empty())
-                    .map(|x| x.parse::<u8>().unwrap().into())
-                    .collect(),
-            );
-            boards.last_mut().unwrap().lines.push(board_line)
-        }
+pub fn input_generator(input: &str) -> Result<Bingo, ParseError> {
+    let blocks = input::blocks(4, input);
+    let (draws_block, board_blocks) = blocks
+        .split_first()
+        .ok_or_else(|| ParseError::new("", "", "draws").at(4, 1))?;
+    if let Some(line) = draws_block.get(1) {
+        return Err(line.error(line.text, "blank line"));
     }
+    let draws = draws_block[0].csv_numbers()?;
+
+    let boards = board_blocks
+        .iter()
+        .map(|block| {
+            let rows = input::grid::<u32>(block)?;
+            if let Some((line, _)) = block
+                .iter()
+                .zip(&rows)
+                .find(|(_, row)| row.len() != rows[0].len())
+            {
+                return Err(line.error(line.text, "row as long as the first"));
+            }
+            Ok(Board::from_rows(rows))
+        })
+        .collect::<Result<_, ParseError>>()?;
+
+    Ok(Bingo { draws, boards })
+}
 
-    (draws, boards)
+fn play(input: &Bingo) -> Vec<Win> {
+    BingoGame::new(
+        input.boards.clone(),
+        &[WinPattern::Rows, WinPattern::Columns],
+        UnmarkedSum,
+    )
+    .play(&input.draws)
 }
 
 #[aoc(day4, part1)]
-pub fn solve_part1(input: &(Vec<u8>, Vec<Board>)) -> u32 {
-    let mut boards = input.1.clone();
-    for draw in input.0.iter() {
-        for board in boards.iter_mut() {
-            if let Some(score) = board.check_draw(*draw) {
-                return score;
-            }
-        }
-    }
-    unreachable!()
+pub fn solve_part1(input: &Bingo) -> Result<Answer, NoWinner> {
+    let win = play(input).first().copied().ok_or(NoWinner)?;
+    Ok(win.score.into())
 }
 
+/// The last boards may win on the same draw, the last of them counts.
 #[aoc(day4, part2)]
-pub fn solve_part2(input: &(Vec<u8>, Vec<Board>)) -> u32 {
-    let mut boards = input.1.clone();
-    for draw in input.0.iter() {
-        let mut incomplete_boards: Vec<&mut Board> =
-            boards.iter_mut().filter(|b| !b.complete).collect();
-        let mut score = None;
-        for board in incomplete_boards.iter_mut() {
-            score = board.check_draw(*draw);
+pub fn solve_part2(input: &Bingo) -> Result<Answer, NoWinner> {
+    let win = play(input).last().copied().ok_or(NoWinner)?;
+    Ok(win.score.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    const EXAMPLE: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7";
+    #[test]
+    fn d4_parts() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1(&input), Ok(4512.into()));
+        assert_eq!(solve_part2(&input), Ok(1924.into()));
+    }
+    #[test]
+    #[should_panic(expected = "mask without any cell")]
+    fn d4_empty_mask() {
+        let board = Board::from_rows(vec![vec![1]]);
+        BingoGame::new(
+            vec![board],
+            &[WinPattern::Mask(vec![vec![false]])],
+            UnmarkedSum,
+        );
+    }
+    #[test]
+    #[should_panic(expected = "mask without any cell")]
+    fn d4_no_mask_row() {
+        let board = Board::from_rows(vec![vec![1]]);
+        BingoGame::new(vec![board], &[WinPattern::Mask(vec![])], UnmarkedSum);
+    }
+    #[test]
+    fn d4_simultaneous_winners() {
+        let input = input_generator("1,2,3\n\n1 2\n7 8\n\n3 2\n9 10\n\n3 5\n1 11").unwrap();
+        let wins: Vec<_> = play(&input)
+            .iter()
+            .map(|w| (w.board, w.draw, w.score))
+            .collect();
+        assert_eq!(wins, vec![(0, 2, 30), (1, 3, 57), (2, 3, 48)]);
+        assert_eq!(solve_part2(&input), Ok(48.into()));
+    }
+    #[test]
+    fn d4_patterns() {
+        let input = input_generator("1,2,3,4,5,6\n\n1 2 3\n4 5 6\n\n4 3\n2 1").unwrap();
+        assert_eq!((input.boards[0].rows(), input.boards[0].columns()), (2, 3));
+        let wins = |patterns: &[WinPattern]| {
+            let mut game = BingoGame::new(input.boards.clone(), patterns, |_: &Board, _| 0);
+            let wins = game.play(&input.draws);
+            wins.iter().map(|w| (w.board, w.draw)).collect::<Vec<_>>()
+        };
+        assert_eq!(wins(&[WinPattern::Rows]), vec![(1, 2), (0, 3)]);
+        assert_eq!(wins(&[WinPattern::Columns]), vec![(1, 3), (0, 4)]);
+        assert_eq!(wins(&[WinPattern::Diagonals]), vec![(1, 3)]);
+        assert_eq!(wins(&[WinPattern::FourCorners]), vec![(1, 4), (0, 6)]);
+        assert_eq!(wins(&[WinPattern::FullCard]), vec![(1, 4), (0, 6)]);
+        let l = WinPattern::Mask(vec![vec![true], vec![true, true]]);
+        assert_eq!(wins(&[l]), vec![(1, 4), (0, 5)]);
+        let too_wide = WinPattern::Mask(vec![vec![false, false, true]]);
+        assert_eq!(wins(&[too_wide]), vec![(0, 3)]);
+
+        let mut game = BingoGame::new(input.boards.clone(), &[WinPattern::Rows], UnmarkedSum);
+        assert_eq!(game.draw(1), vec![]);
+        assert_eq!(game.draw(2)[0].score, (4 + 3) * 2);
+        assert!(game.has_won(1) && !game.has_won(0));
+        assert_eq!(game.find(4), &[(0, 1, 0), (1, 0, 0)]);
+        assert_eq!(game.find(7), &[]);
+
+        // a number twice on a board counts once for the win
+        let input = input_generator("5,9\n\n5 1\n1 5\n\n9 5\n5 1").unwrap();
+        let mut game = BingoGame::new(input.boards, &[WinPattern::Diagonals], UnmarkedSum);
+        assert_eq!(
+            game.play(&input.draws),
+            vec![
+                Win {
+                    board: 0,
+                    draw: 5,
+                    score: 10
+                },
+                Win {
+                    board: 1,
+                    draw: 5,
+                    score: 50
+                }
+            ]
+        );
+
+        assert_eq!(
+            input_generator("1\n\n1 2\n3").unwrap_err().to_string(),
+            "day4 line 4 column 1: expected `row as long as the first`, found `3`"
+        );
+    }
+    /// Marks and rescans every row and column of every board after each draw.
+    fn naive_wins(boards: &[Vec<Vec<u32>>], draws: &[u32]) -> Vec<Win> {
+        let mut marked: Vec<Vec<Vec<bool>>> = boards
+            .iter()
+            .map(|b| vec![vec![false; b[0].len()]; b.len()])
+            .collect();
+        let mut won = vec![false; boards.len()];
+        let mut wins = vec![];
+        for draw in draws {
+            for (i, board) in boards.iter().enumerate() {
+                if won[i] {
+                    continue;
+                }
+                for (r, row) in board.iter().enumerate() {
+                    for (c, number) in row.iter().enumerate() {
+                        marked[i][r][c] |= number == draw;
+                    }
+                }
+                let m = &marked[i];
+                let row_done = m.iter().any(|row| row.iter().all(|x| *x));
+                let column_done = (0..m[0].len()).any(|c| m.iter().all(|row| row[c]));
+                if row_done || column_done {
+                    won[i] = true;
+                    let unmarked: u64 = board
+                        .iter()
+                        .flatten()
+                        .zip(m.iter().flatten())
+                        .filter(|(_, marked)| !**marked)
+                        .map(|(n, _)| *n as u64)
+                        .sum();
+                    wins.push(Win {
+                        board: i,
+                        draw: *draw,
+                        score: unmarked * *draw as u64,
+                    });
+                }
+            }
         }
-        if incomplete_boards.len() == 1 && score.is_some() {
-            return score.unwrap();
+        wins
+    }
+    fn board() -> impl Strategy<Value = Vec<Vec<u32>>> {
+        (1..5usize, 1..5usize).prop_flat_map(|(rows, columns)| {
+            prop::collection::vec(prop::collection::vec(0..20u32, columns), rows)
+        })
+    }
+    proptest! {
+        #[test]
+        fn d4_game_matches_rescan(
+            boards in prop::collection::vec(board(), 1..6),
+            draws in prop::collection::vec(0..20u32, 0..40),
+        ) {
+            let mut game = BingoGame::new(
+                boards.iter().cloned().map(Board::from_rows).collect(),
+                &[WinPattern::Rows, WinPattern::Columns],
+                UnmarkedSum,
+            );
+            prop_assert_eq!(game.play(&draws), naive_wins(&boards, &draws));
         }
     }
-    unreachable!()
 }
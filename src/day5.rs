@@ -1,7 +1,9 @@
-use std::{cmp::max, fmt::Debug, mem, num::ParseIntError, str::FromStr};
+use std::{cmp::max, fmt::Debug, mem, str::FromStr};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::{answer::Answer, error::ParseError, input};
+
 #[derive(Clone, Copy)]
 struct Coordinate {
     x: u16,
@@ -21,16 +23,15 @@ impl Coordinate {
 }
 
 impl FromStr for Coordinate {
-    type Err = ParseIntError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let input: Vec<u16> = s
-            .split(",")
-            .map(|x| x.parse::<u16>())
-            .collect::<Result<Vec<u16>, ParseIntError>>()?;
+        let (x, y) = s
+            .split_once(',')
+            .ok_or_else(|| ParseError::new(s, s, "x,y"))?;
         Ok(Self {
-            x: input[0],
-            y: input[1],
+            x: x.parse().map_err(|_| ParseError::new(s, x, "x"))?,
+            y: y.parse().map_err(|_| ParseError::new(s, y, "y"))?,
         })
     }
 }
@@ -44,6 +45,10 @@ impl Segment {
         self.0.is_horizontally_aligned_with(&self.1)
     }
 
+    fn is_diagonal(&self) -> bool {
+        self.0.x.abs_diff(self.1.x) == self.0.y.abs_diff(self.1.y)
+    }
+
     fn sort(mut self) -> Self {
         if self.0.x > self.1.x || self.0.x == self.1.x && self.0.y > self.1.y {
             mem::swap(&mut self.0, &mut self.1);
@@ -53,14 +58,19 @@ impl Segment {
 }
 
 impl FromStr for Segment {
-    type Err = ParseIntError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let input = s
-            .split(" -> ")
-            .map(|x| x.parse::<Coordinate>())
-            .collect::<Result<Vec<Coordinate>, ParseIntError>>()?;
-        Ok(Self(input[0], input[1]).sort())
+        let (start, end) = s
+            .split_once(" -> ")
+            .ok_or_else(|| ParseError::new(s, s, "x,y -> x,y"))?;
+        let start = start.parse().map_err(|e: ParseError| e.within(s, start))?;
+        let end = end.parse().map_err(|e: ParseError| e.within(s, end))?;
+        let segment = Self(start, end);
+        if !(segment.is_horizontal() || segment.is_vertical() || segment.is_diagonal()) {
+            return Err(ParseError::new(s, s, "horizontal, vertical or 45° segment"));
+        }
+        Ok(segment.sort())
     }
 }
 
@@ -124,8 +134,8 @@ impl Grid {
             let col = &mut self.columns[segment.0.x as usize];
             let start = segment.0.y as usize;
             let end = (segment.1.y + 1) as usize;
-            for y in start..end {
-                col[y] += 1;
+            for cell in &mut col[start..end] {
+                *cell += 1;
             }
         } else if segment.is_horizontal() {
             let y = segment.0.y as usize;
@@ -157,7 +167,7 @@ impl Grid {
 
 impl Debug for Grid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "")?;
+        writeln!(f)?;
         for y in 0..self.get_columns_len() {
             for c in &self.columns {
                 if c[y] == 0 {
@@ -166,22 +176,19 @@ impl Debug for Grid {
                     write!(f, "{}", c[y])?;
                 }
             }
-            writeln!(f, "")?;
+            writeln!(f)?;
         }
         Ok(())
     }
 }
 
 #[aoc_generator(day5)]
-pub fn input_generator(input: &str) -> Vec<Segment> {
-    input
-        .lines()
-        .map(|line| line.trim().parse().unwrap())
-        .collect::<Vec<_>>()
+pub fn input_generator(input: &str) -> Result<Vec<Segment>, ParseError> {
+    input::lines(5, input).map(|line| line.parse()).collect()
 }
 
 #[aoc(day5, part1)]
-pub fn solve_part1(input: &[Segment]) -> usize {
+pub fn solve_part1(input: &[Segment]) -> Answer {
     let grid = input
         .iter()
         .filter(|s| s.is_horizontal() || s.is_vertical())
@@ -190,22 +197,59 @@ pub fn solve_part1(input: &[Segment]) -> usize {
             grid
         });
 
-    grid.count_overlap()
+    grid.count_overlap().into()
 }
 
 #[aoc(day5, part2)]
-pub fn solve_part2(input: &[Segment]) -> usize {
+pub fn solve_part2(input: &[Segment]) -> Answer {
     let grid = input.iter().fold(Grid::with_diagonals(), |mut grid, s| {
         grid.add_segment(s);
         grid
     });
 
-    grid.count_overlap()
+    grid.count_overlap().into()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    /// Counts overlaps point by point, without the grid.
+    fn overlaps(segments: &[(i32, i32, i32, i32)], diagonals: bool) -> usize {
+        let mut points = HashMap::new();
+        for (x1, y1, x2, y2) in segments.iter().copied() {
+            if !diagonals && x1 != x2 && y1 != y2 {
+                continue;
+            }
+            let (dx, dy) = ((x2 - x1).signum(), (y2 - y1).signum());
+            let len = (x2 - x1).abs().max((y2 - y1).abs());
+            for i in 0..=len {
+                *points.entry((x1 + i * dx, y1 + i * dy)).or_insert(0) += 1;
+            }
+        }
+        points.values().filter(|n| **n > 1).count()
+    }
+
+    /// Horizontal, vertical or 45° diagonal segments, in any direction.
+    fn segment() -> impl Strategy<Value = (i32, i32, i32, i32)> {
+        (0..30i32, 20..40i32, 0..20i32, 0..4u8, any::<bool>()).prop_map(
+            |(x, y, len, kind, reversed)| {
+                let (x2, y2) = match kind {
+                    0 => (x + len, y),
+                    1 => (x, y + len),
+                    2 => (x + len, y + len),
+                    _ => (x + len, y - len),
+                };
+                if reversed {
+                    (x2, y2, x, y)
+                } else {
+                    (x, y, x2, y2)
+                }
+            },
+        )
+    }
     #[test]
     fn d5_part2() {
         let input = input_generator(
@@ -219,7 +263,34 @@ mod tests {
 3,4 -> 1,4
 0,0 -> 8,8
 5,5 -> 8,2",
+        )
+        .unwrap();
+        assert_eq!(solve_part2(input.as_slice()), 12.into());
+    }
+    proptest! {
+        #[test]
+        fn d5_grid_matches_points(segments in prop::collection::vec(segment(), 1..30)) {
+            let text = segments
+                .iter()
+                .map(|(x1, y1, x2, y2)| format!("{},{} -> {},{}", x1, y1, x2, y2))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let input = input_generator(&text).unwrap();
+            prop_assert_eq!(solve_part1(&input), overlaps(&segments, false).into());
+            prop_assert_eq!(solve_part2(&input), overlaps(&segments, true).into());
+        }
+    }
+    #[test]
+    fn d5_parse_error() {
+        let error = input_generator("0,9 -> 5,9\n8,0 -> 0,x").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "day5 line 2 column 10: expected `y`, found `x`"
+        );
+        let error = input_generator("0,9 -> 5,9\n0,0 -> 5,2").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "day5 line 2 column 1: expected `horizontal, vertical or 45° segment`, found `0,0 -> 5,2`"
         );
-        assert_eq!(solve_part2(input.as_slice()), 12);
     }
 }
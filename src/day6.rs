@@ -1,7 +1,9 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::{answer::Answer, error::ParseError, input};
+
 #[derive(Clone)]
 pub struct Fish(u8);
 
@@ -21,6 +23,17 @@ impl Fish {
     }
 }
 
+impl FromStr for Fish {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(timer) if timer <= 8 => Ok(Fish(timer)),
+            _ => Err(ParseError::new(s, s, "timer 0-8")),
+        }
+    }
+}
+
 impl fmt::Debug for Fish {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -84,59 +97,79 @@ impl fmt::Debug for Colony {
         for g in self.groups {
             write!(f, "{:?} ", g)?;
         }
-        writeln!(f, "")?;
+        writeln!(f)?;
         Ok(())
     }
 }
 
 #[aoc_generator(day6)]
-pub fn input_generator(input: &str) -> Vec<Fish> {
-    input
-        .trim()
-        .split(",")
-        .map(|n| Fish(n.parse().unwrap()))
-        .collect::<Vec<_>>()
+pub fn input_generator(input: &str) -> Result<Vec<Fish>, ParseError> {
+    input::lines(6, input).try_fold(vec![], |mut fishes, line| {
+        for n in line.csv() {
+            fishes.push(
+                n.parse()
+                    .map_err(|e: ParseError| e.within(line.text, n).at(6, line.number))?,
+            );
+        }
+        Ok(fishes)
+    })
 }
 
-#[aoc(day6, part1)]
-pub fn solve_part1(input: &[Fish]) -> usize {
+/// Simulates every single fish, the population grows exponentially.
+fn simulate_fishes(input: &[Fish], days: usize) -> usize {
     let mut colony = input.to_vec();
-    for _ in 0..80 {
-        let mut new: u16 = 0;
+    for _ in 0..days {
+        let mut new: usize = 0;
         colony.iter_mut().for_each(|f: &mut Fish| {
             let gave_birth = (*f).age();
             if gave_birth {
                 new += 1;
             }
         });
-        colony.extend(vec![Fish::new(); new.into()]);
+        colony.extend(vec![Fish::new(); new]);
     }
 
     colony.len()
 }
 
-#[aoc(day6, part2)]
-pub fn solve_part2(input: &[Fish]) -> u64 {
+fn simulate_colony(input: &[Fish], days: usize) -> u64 {
     let mut colony = Colony::new(input);
-    println!("{:?}", colony);
-    for _ in 0..256 {
+    for _ in 0..days {
         colony.age();
     }
 
     colony.count()
 }
 
+#[aoc(day6, part1)]
+pub fn solve_part1(input: &[Fish]) -> Answer {
+    simulate_fishes(input, 80).into()
+}
+
+#[aoc(day6, part2)]
+pub fn solve_part2(input: &[Fish]) -> Answer {
+    simulate_colony(input, 256).into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     #[test]
     fn d6_part1() {
-        let input = input_generator("3,4,3,1,2");
-        assert_eq!(solve_part1(input.as_slice()), 5934);
+        let input = input_generator("3,4,3,1,2").unwrap();
+        assert_eq!(solve_part1(input.as_slice()), 5934.into());
     }
     #[test]
     fn d6_part2() {
-        let input = input_generator("3,4,3,1,2");
-        assert_eq!(solve_part2(input.as_slice()), 26984457539);
+        let input = input_generator("3,4,3,1,2").unwrap();
+        assert_eq!(solve_part2(input.as_slice()), 26984457539_u64.into());
+    }
+    proptest! {
+        #[test]
+        fn d6_colony_matches_fishes(timers in prop::collection::vec(0..=8u8, 0..20), days in 0..100usize) {
+            let fishes: Vec<Fish> = timers.into_iter().map(Fish).collect();
+            prop_assert_eq!(simulate_colony(&fishes, days), simulate_fishes(&fishes, days) as u64);
+        }
     }
 }
@@ -1,60 +1,99 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::{answer::Answer, error::ParseError, input};
+
+/// There must be at least one crab.
 #[aoc_generator(day7)]
-pub fn input_generator(input: &str) -> Vec<u16> {
-    input
-        .trim()
-        .split(",")
-        .map(|n| n.parse().unwrap())
-        .collect::<Vec<_>>()
+pub fn input_generator(input: &str) -> Result<Vec<u16>, ParseError> {
+    let crabs = input::csv_numbers(7, input)?;
+    if crabs.is_empty() {
+        return Err(ParseError::new("", "", "number").at(7, 1));
+    }
+    Ok(crabs)
 }
 
 fn compute_score(input: &[u16], pivot: u16) -> usize {
-    input
-        .iter()
-        .map(|pos| (*pos as i16 - pivot as i16).abs() as usize)
-        .sum()
+    input.iter().map(|pos| pos.abs_diff(pivot) as usize).sum()
 }
 
 fn compute_score2(input: &[u16], pivot: u16) -> usize {
     input
         .iter()
         .map(|pos| {
-            let n = (*pos as i16 - pivot as i16).abs() as usize;
+            let n = pos.abs_diff(pivot) as usize;
             n * (n + 1) / 2
         })
         .sum()
 }
 
 #[aoc(day7, part1)]
-pub fn solve_part1(input: &[u16]) -> usize {
+pub fn solve_part1(input: &[u16]) -> Answer {
     input
         .iter()
         .map(|pos| compute_score(input, *pos))
         .min()
         .unwrap()
+        .into()
+}
+
+/// The median minimizes the sum of distances.
+#[aoc(day7, part1, median)]
+pub fn solve_part1_median(input: &[u16]) -> Answer {
+    let mut sorted = input.to_vec();
+    sorted.sort_unstable();
+    compute_score(input, sorted[sorted.len() / 2]).into()
 }
 
 #[aoc(day7, part2)]
-pub fn solve_part2(input: &[u16]) -> usize {
-    let max_pos = *input.iter().max().unwrap() as usize;
-    (0..max_pos)
-        .map(|pos| compute_score2(input, pos as u16))
+pub fn solve_part2(input: &[u16]) -> Answer {
+    let max_pos = *input.iter().max().unwrap();
+    (0..=max_pos)
+        .map(|pos| compute_score2(input, pos))
         .min()
         .unwrap()
+        .into()
+}
+
+/// The best position is less than 1/2 away from the mean, so it's either its floor or its ceiling.
+#[aoc(day7, part2, mean)]
+pub fn solve_part2_mean(input: &[u16]) -> Answer {
+    let sum: usize = input.iter().map(|pos| *pos as usize).sum();
+    let floor = sum / input.len();
+    let ceil = sum.div_ceil(input.len());
+    [floor, ceil]
+        .iter()
+        .map(|pos| compute_score2(input, *pos as u16))
+        .min()
+        .unwrap()
+        .into()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     #[test]
     fn d7_part1() {
-        let input = input_generator("16,1,2,0,4,2,7,1,2,14");
-        assert_eq!(solve_part1(input.as_slice()), 37);
+        let input = input_generator("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!(solve_part1(input.as_slice()), 37.into());
     }
     #[test]
     fn d7_part2() {
-        let input = input_generator("16,1,2,0,4,2,7,1,2,14");
-        assert_eq!(solve_part2(input.as_slice()), 168);
+        let input = input_generator("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!(solve_part2(input.as_slice()), 168.into());
+    }
+    #[test]
+    fn d7_empty() {
+        assert_eq!(
+            input_generator("\n").unwrap_err().to_string(),
+            "day7 line 1 column 1: expected `number`, found ``"
+        );
+    }
+    proptest! {
+        #[test]
+        fn d7_median_and_mean_match_scans(input in prop::collection::vec(0..2000u16, 1..50)) {
+            prop_assert_eq!(solve_part1_median(&input), solve_part1(&input));
+            prop_assert_eq!(solve_part2_mean(&input), solve_part2(&input));
+        }
     }
 }
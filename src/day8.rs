@@ -1,10 +1,14 @@
-use std::{
-    collections::{HashMap, HashSet},
-    ops::Sub,
-};
+use std::{collections::HashSet, error::Error, fmt, ops::Sub};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::{answer::Answer, error::ParseError, input};
+
+/// Segments lit by each digit on a correctly wired display.
+pub const DIGITS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
 #[derive(PartialEq, Clone)]
 struct Pattern(HashSet<char>);
 
@@ -29,7 +33,7 @@ impl Sub<&Pattern> for Pattern {
         Pattern(
             self.0
                 .difference(&rhs.0)
-                .map(|x| *x)
+                .copied()
                 .collect::<HashSet<char>>(),
         )
     }
@@ -42,7 +46,7 @@ impl Sub<&Pattern> for &Pattern {
         Pattern(
             self.0
                 .difference(&rhs.0)
-                .map(|x| *x)
+                .copied()
                 .collect::<HashSet<char>>(),
         )
     }
@@ -58,7 +62,6 @@ impl TryInto<char> for Pattern {
         if self.0.len() == 1 {
             Ok(*self.0.iter().next().unwrap())
         } else {
-            println!("Error: {:?}", self.0);
             Err(SegmentError)
         }
     }
@@ -70,114 +73,204 @@ pub struct Input {
     outputs: Vec<Pattern>,
 }
 
-fn parse_part(part: &str, sorted: bool) -> Vec<Pattern> {
+fn parse_part(
+    part: &str,
+    len: usize,
+    sorted: bool,
+    expected: &'static str,
+) -> Result<Vec<Pattern>, ParseError> {
     let mut ret = part
-        .split(" ")
+        .split(' ')
         .map(|s| {
-            let mut l = s.to_owned().chars().collect::<HashSet<char>>();
-            Pattern(l)
+            if s.is_empty() || s.chars().any(|c| !('a'..='g').contains(&c)) {
+                Err(ParseError::new(part, s, "a-g segments"))
+            } else {
+                Ok(Pattern(s.chars().collect::<HashSet<char>>()))
+            }
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, _>>()?;
+    if ret.len() != len {
+        return Err(ParseError::new(part, part, expected));
+    }
     if sorted {
-        ret.sort_unstable_by(|a, b| a.len().cmp(&b.len()));
+        ret.sort_unstable_by_key(|p| p.len());
     }
-    ret
+    Ok(ret)
+}
+
+/// The patterns of the ten digits: distinct, of 2, 3, 4, 5, 5, 5, 6, 6, 6 and 7 segments.
+fn check_digits(part: &str, patterns: &[Pattern]) -> Result<(), ParseError> {
+    let sizes: Vec<usize> = patterns.iter().map(Pattern::len).collect();
+    let distinct = patterns
+        .iter()
+        .enumerate()
+        .all(|(i, p)| !patterns[..i].contains(p));
+    if sizes != [2, 3, 4, 5, 5, 5, 6, 6, 6, 7] || !distinct {
+        return Err(ParseError::new(part, part, "patterns of the 10 digits"));
+    }
+    Ok(())
 }
 
 #[aoc_generator(day8)]
-pub fn input_generator(input: &str) -> Vec<Input> {
-    input
-        .lines()
+pub fn input_generator(input: &str) -> Result<Vec<Input>, ParseError> {
+    input::lines(8, input)
         .map(|line| {
-            let parts = line.trim().split(" | ").collect::<Vec<_>>();
-            Input {
-                patterns: parse_part(parts[0], true),
-                outputs: parse_part(parts[1], false),
-            }
+            let (patterns, outputs) = line
+                .text
+                .trim_start()
+                .split_once(" | ")
+                .ok_or_else(|| line.error(line.text, "<patterns> | <outputs>"))?;
+            let digits = parse_part(patterns, 10, true, "10 patterns")
+                .and_then(|digits| check_digits(patterns, &digits).map(|_| digits))
+                .map_err(|e| e.within(line.text, patterns).at(8, line.number))?;
+            Ok(Input {
+                patterns: digits,
+                outputs: parse_part(outputs, 4, false, "4 outputs")
+                    .map_err(|e| e.within(line.text, outputs).at(8, line.number))?,
+            })
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
 #[aoc(day8, part1)]
-pub fn solve_part1(input: &[Input]) -> usize {
+pub fn solve_part1(input: &[Input]) -> Answer {
     input
         .iter()
         .map(|x| {
             x.outputs
                 .iter()
-                .filter(|s| match s.len() {
-                    2 | 3 | 4 | 7 => true,
-                    _ => false,
-                })
+                .filter(|s| matches!(s.len(), 2 | 3 | 4 | 7))
                 .count()
         })
-        .sum()
+        .sum::<usize>()
+        .into()
+}
+
+/// Value shown by the outputs, `None` if the patterns are not a seven-segment wiring.
+fn decode(entry: &Input) -> Option<usize> {
+    let mut digits: Vec<Option<Pattern>> = vec![None; 10];
+    // patterns are sorted by size
+
+    digits[1] = Some(entry.patterns[0].clone()); // size 2 -> 1
+    digits[4] = Some(entry.patterns[2].clone()); // size 4 -> 4
+    digits[7] = Some(entry.patterns[1].clone()); // size 3 -> 7
+    digits[8] = Some(entry.patterns[9].clone()); // size 7 -> 8
+
+    // if size 5 (2,3,5) contains 1 -> 3
+    digits[3] = entry.patterns[3..6]
+        .iter()
+        .find(|p| p.contains(&entry.patterns[0]))
+        .cloned();
+    let three = digits[3].clone()?;
+    // if size 6 (0,6,9) contains 3 -> 9
+    digits[9] = entry.patterns[6..9]
+        .iter()
+        .find(|p| p.contains(&three))
+        .cloned();
+    let nine = digits[9].clone()?;
+    let bottom_left: char = (&entry.patterns[9] - &nine).try_into().ok()?;
+    for p in entry.patterns[3..6].iter() {
+        // if size 5 (2,3,5) contains bottom left -> 2
+        if p.contains_char(&bottom_left) {
+            digits[2] = Some(p.clone());
+        } else if *p != three {
+            digits[5] = Some(p.clone());
+        }
+    }
+    let top_right: char = (&nine - digits[5].as_ref()?).try_into().ok()?;
+    for p in entry.patterns[6..9].iter() {
+        // if size 6 (0,6,9) does not contains top_right -> 6
+        if !p.contains_char(&top_right) {
+            digits[6] = Some(p.clone());
+        } else if *p != nine {
+            digits[0] = Some(p.clone());
+        }
+    }
+    entry.outputs.iter().try_fold(0, |value, o| {
+        let digit = digits.iter().position(|p| p.as_ref() == Some(o))?;
+        Some(value * 10 + digit)
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownWiring {
+    /// 1-based, like input lines.
+    pub entry: usize,
 }
 
+impl fmt::Display for UnknownWiring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "entry {} does not match a seven-segment wiring",
+            self.entry
+        )
+    }
+}
+
+impl Error for UnknownWiring {}
+
 #[aoc(day8, part2)]
-pub fn solve_part2(input: &[Input]) -> usize {
+pub fn solve_part2(input: &[Input]) -> Result<Answer, UnknownWiring> {
     input
         .iter()
-        .map(|i| {
-            let mut digits: Vec<Option<Pattern>> = vec![None; 10];
-            // patterns are sorted by size
-
-            digits[1] = Some(i.patterns[0].clone()); // size 2 -> 1
-            digits[4] = Some(i.patterns[2].clone()); // size 4 -> 4
-            digits[7] = Some(i.patterns[1].clone()); // size 3 -> 7
-            digits[8] = Some(i.patterns[9].clone()); // size 7 -> 8
-
-            let top: char = (digits[7].as_ref().unwrap() - digits[1].as_ref().unwrap())
-                .try_into()
-                .unwrap();
-            for p in i.patterns[3..6].iter() {
-                // if size 5 (2,3,5) contains 1 -> 3
-                if p.contains(digits[1].as_ref().unwrap()) {
-                    digits[3] = Some(p.clone());
-                    break;
-                }
-            }
-            for p in i.patterns[6..9].iter() {
-                // if size 6 (0,6,9) contains 3 -> 9
-                if p.contains(&digits[3].as_ref().unwrap()) {
-                    digits[9] = Some(p.clone());
-                    break;
-                }
-            }
-            let bottom_left: char = (digits[8].as_ref().unwrap() - digits[9].as_ref().unwrap())
-                .try_into()
-                .unwrap();
-            for p in i.patterns[3..6].iter() {
-                // if size 5 (2,3,5) contains bottom left -> 2
-                if p.contains_char(&bottom_left) {
-                    digits[2] = Some(p.clone());
-                } else if *p != digits[3].clone().unwrap() {
-                    digits[5] = Some(p.clone());
-                }
-            }
-            let top_right: char = (digits[9].as_ref().unwrap() - digits[5].as_ref().unwrap())
-                .try_into()
-                .unwrap();
-            for p in i.patterns[6..9].iter() {
-                // if size 6 (0,6,9) does not contains top_right -> 6
-                if !p.contains_char(&top_right) {
-                    digits[6] = Some(p.clone());
-                } else if *p != digits[9].clone().unwrap() {
-                    digits[0] = Some(p.clone());
-                }
-            }
-            i.outputs
-                .iter()
-                .rev()
-                .enumerate()
-                .map(|(i, o)| {
-                    digits
-                        .iter()
-                        .position(|p| *o == p.clone().unwrap())
-                        .unwrap()
-                        * 10_usize.pow(i as u32)
-                })
-                .sum::<usize>()
-        })
-        .sum()
+        .enumerate()
+        .map(|(i, entry)| decode(entry).ok_or(UnknownWiring { entry: i + 1 }))
+        .sum::<Result<usize, _>>()
+        .map(Answer::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// A display whose wires are shuffled, with the value it shows.
+    fn display() -> impl Strategy<Value = (String, usize)> {
+        (
+            Just("abcdefg".chars().collect::<Vec<_>>()).prop_shuffle(),
+            Just((0..10).collect::<Vec<usize>>()).prop_shuffle(),
+            prop::collection::vec(0..10usize, 4),
+        )
+            .prop_map(|(wiring, order, outputs)| {
+                let scramble = |digit: usize| -> String {
+                    DIGITS[digit]
+                        .chars()
+                        .map(|c| wiring[(c as u8 - b'a') as usize])
+                        .collect()
+                };
+                let patterns: Vec<String> = order.into_iter().map(scramble).collect();
+                let shown: Vec<String> = outputs.iter().copied().map(scramble).collect();
+                let value = outputs.iter().fold(0, |value, digit| value * 10 + digit);
+                (
+                    format!("{} | {}", patterns.join(" "), shown.join(" ")),
+                    value,
+                )
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn d8_decodes_scrambled_displays(displays in prop::collection::vec(display(), 1..10)) {
+            let text = displays.iter().map(|(line, _)| line.as_str()).collect::<Vec<_>>().join("\n");
+            let input = input_generator(&text).unwrap();
+            let total: usize = displays.iter().map(|(_, value)| value).sum();
+            prop_assert_eq!(solve_part2(&input), Ok(total.into()));
+        }
+    }
+    #[test]
+    fn d8_invalid_wiring() {
+        assert_eq!(
+            input_generator("a a a a a a a a a a | a a a a")
+                .err()
+                .unwrap()
+                .to_string(),
+            "day8 line 1 column 1: expected `patterns of the 10 digits`, found `a a a a a a a a a a`"
+        );
+        let input = input_generator(
+            "ab abc abcd acdef bcdeg cdefg abcdef abcdeg abcdfg abcdefg | ab ab ab ab",
+        )
+        .unwrap();
+        assert_eq!(solve_part2(&input), Err(UnknownWiring { entry: 1 }));
+    }
 }
@@ -0,0 +1,60 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub expected: &'static str,
+}
+
+/// 1-based column of `token` inside `source`, `token` being a subslice of `source`.
+fn column(source: &str, token: &str) -> usize {
+    let start = source.as_ptr() as usize;
+    let position = token.as_ptr() as usize;
+    if position >= start && position <= start + source.len() {
+        position - start + 1
+    } else {
+        1
+    }
+}
+
+impl ParseError {
+    /// Error on `token`, found while parsing `source`.
+    /// Day and line are unknown until the generator calls `at`.
+    pub fn new(source: &str, token: &str, expected: &'static str) -> Self {
+        Self {
+            day: 0,
+            line: 0,
+            column: column(source, token),
+            token: token.to_owned(),
+            expected,
+        }
+    }
+
+    /// Moves the column from `source` coordinates to `outer` coordinates,
+    /// `source` being a subslice of `outer`.
+    pub fn within(mut self, outer: &str, source: &str) -> Self {
+        self.column += column(outer, source) - 1;
+        self
+    }
+
+    pub fn at(mut self, day: u8, line: usize) -> Self {
+        self.day = day;
+        self.line = line;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day{} line {} column {}: expected `{}`, found `{}`",
+            self.day, self.line, self.column, self.expected, self.token
+        )
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,216 @@
+//! Synthetic puzzle inputs, in the same text format as the real ones, to stress parsers and solvers.
+
+use std::fmt::Write;
+
+use crate::day8;
+
+/// SplitMix64, so that a seed gives the same input on every platform.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`, `n` must not be 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+}
+
+/// `count` depths, drifting down like the real sonar sweep.
+pub fn day1(rng: &mut Rng, count: usize) -> String {
+    let mut depth: u16 = rng.below(200) as u16 + 100;
+    let mut input = String::new();
+    for _ in 0..count {
+        depth = depth.saturating_add(rng.below(20) as u16).saturating_sub(8);
+        writeln!(input, "{}", depth).unwrap();
+    }
+    input
+}
+
+/// `count` commands, never going above the surface.
+pub fn day2(rng: &mut Rng, count: usize) -> String {
+    let mut depth = 0;
+    let mut input = String::new();
+    for _ in 0..count {
+        let x = rng.below(9) + 1;
+        let verb = match rng.below(3) {
+            0 => "forward",
+            1 => "down",
+            _ if depth < x => "down",
+            _ => "up",
+        };
+        match verb {
+            "down" => depth += x,
+            "up" => depth -= x,
+            _ => {}
+        }
+        writeln!(input, "{} {}", verb, x).unwrap();
+    }
+    input
+}
+
+/// `count` readings of up to 256 bits, all distinct like in the real reports.
+pub fn day3(rng: &mut Rng, count: usize, width: usize) -> String {
+    let width = width.clamp(1, 256);
+    let count = count.min(1 << width.min(63));
+    let mut readings = std::collections::HashSet::new();
+    while readings.len() < count {
+        let reading: String = (0..width)
+            .map(|_| if rng.below(2) == 0 { '0' } else { '1' })
+            .collect();
+        readings.insert(reading);
+    }
+    let mut readings: Vec<String> = readings.into_iter().collect();
+    readings.sort_unstable();
+    rng.shuffle(&mut readings);
+    readings.iter().map(|r| format!("{}\n", r)).collect()
+}
+
+/// `count` boards of `size`×`size` distinct numbers; every number is drawn so every board wins.
+pub fn day4(rng: &mut Rng, count: usize, size: usize) -> String {
+    let size = size.clamp(1, 16);
+    let range = (size * size).max(100);
+    let mut numbers: Vec<usize> = (0..range).collect();
+    rng.shuffle(&mut numbers);
+    let draws: Vec<String> = numbers.iter().map(usize::to_string).collect();
+    let mut input = draws.join(",");
+    input.push('\n');
+    for _ in 0..count {
+        rng.shuffle(&mut numbers);
+        input.push('\n');
+        for row in numbers[..size * size].chunks(size) {
+            let row: Vec<String> = row.iter().map(|n| format!("{:>2}", n)).collect();
+            writeln!(input, "{}", row.join(" ")).unwrap();
+        }
+    }
+    input
+}
+
+/// `count` horizontal, vertical or diagonal segments inside a `size`×`size` grid.
+pub fn day5(rng: &mut Rng, count: usize, size: usize) -> String {
+    let size = size.clamp(1, u16::MAX as usize) as u64;
+    let mut input = String::new();
+    for _ in 0..count {
+        let (x1, y1) = (rng.below(size), rng.below(size));
+        let (x2, y2) = match rng.below(3) {
+            0 => (rng.below(size), y1),
+            1 => (x1, rng.below(size)),
+            _ => {
+                // going right, and either down or up as far as the grid allows
+                let down = rng.below(2) == 0;
+                let room = if down { size - 1 - y1 } else { y1 }.min(size - 1 - x1);
+                let len = rng.below(room + 1);
+                (x1 + len, if down { y1 + len } else { y1 - len })
+            }
+        };
+        writeln!(input, "{},{} -> {},{}", x1, y1, x2, y2).unwrap();
+    }
+    input
+}
+
+/// `count` lanternfish timers.
+pub fn day6(rng: &mut Rng, count: usize) -> String {
+    let timers: Vec<String> = (0..count).map(|_| (rng.below(5) + 1).to_string()).collect();
+    timers.join(",") + "\n"
+}
+
+/// `count` crab positions in `0..size`.
+pub fn day7(rng: &mut Rng, count: usize, size: usize) -> String {
+    let size = size.clamp(1, u16::MAX as usize + 1) as u64;
+    let positions: Vec<String> = (0..count).map(|_| rng.below(size).to_string()).collect();
+    positions.join(",") + "\n"
+}
+
+/// Ground truth of a generated day 8 entry.
+#[derive(Debug, Clone)]
+pub struct ScrambledDisplay {
+    /// `wiring[i]` is the wire lighting segment `'a' + i`.
+    pub wiring: [char; 7],
+    /// The 4 digit value shown by the outputs.
+    pub value: usize,
+}
+
+/// `count` entries, each with its own random wiring.
+pub fn day8(rng: &mut Rng, count: usize) -> (String, Vec<ScrambledDisplay>) {
+    let mut input = String::new();
+    let mut displays = vec![];
+    for _ in 0..count {
+        let mut wiring = ['a', 'b', 'c', 'd', 'e', 'f', 'g'];
+        rng.shuffle(&mut wiring);
+        let scramble = |digit: usize, rng: &mut Rng| {
+            let mut wires: Vec<char> = day8::DIGITS[digit]
+                .bytes()
+                .map(|s| wiring[(s - b'a') as usize])
+                .collect();
+            rng.shuffle(&mut wires);
+            wires.into_iter().collect::<String>()
+        };
+        let mut order: Vec<usize> = (0..10).collect();
+        rng.shuffle(&mut order);
+        let patterns: Vec<String> = order.into_iter().map(|d| scramble(d, rng)).collect();
+        let outputs: Vec<usize> = (0..4).map(|_| rng.below(10) as usize).collect();
+        let shown: Vec<String> = outputs.iter().map(|d| scramble(*d, rng)).collect();
+        writeln!(input, "{} | {}", patterns.join(" "), shown.join(" ")).unwrap();
+        displays.push(ScrambledDisplay {
+            wiring,
+            value: outputs.iter().fold(0, |value, digit| value * 10 + digit),
+        });
+    }
+    (input, displays)
+}
+
+/// Input for `day`; `width` is the bit width for day 3, the board size for day 4
+/// and the grid size for days 5 and 7, it is ignored by the other days.
+pub fn generate(day: u8, rng: &mut Rng, count: usize, width: Option<usize>) -> Option<String> {
+    Some(match day {
+        1 => day1(rng, count),
+        2 => day2(rng, count),
+        3 => day3(rng, count, width.unwrap_or(12)),
+        4 => day4(rng, count, width.unwrap_or(5)),
+        5 => day5(rng, count, width.unwrap_or(1000)),
+        6 => day6(rng, count),
+        7 => day7(rng, count, width.unwrap_or(2000)),
+        8 => day8(rng, count).0,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{answer::Answer, day8, registry::registry};
+    #[test]
+    fn generated_inputs_are_solvable() {
+        let mut rng = Rng::new(2021);
+        for solution in registry() {
+            let input = generate(solution.day(), &mut rng, 50, None).unwrap();
+            let input = input.trim_end_matches('\n');
+            if let Err(e) = solution.run(input) {
+                panic!("{}: {}\n{}", solution.name(), e, input);
+            }
+        }
+    }
+    #[test]
+    fn day8_ground_truth() {
+        let (input, displays) = day8(&mut Rng::new(8), 20);
+        let total: usize = displays.iter().map(|d| d.value).sum();
+        let input = day8::input_generator(&input).unwrap();
+        assert_eq!(day8::solve_part2(&input), Ok(Answer::from(total)));
+    }
+}
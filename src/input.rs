@@ -0,0 +1,115 @@
+use std::str::FromStr;
+
+use crate::error::ParseError;
+
+/// Strips the BOM, then the trailing whitespace and blank lines.
+/// CRLF line endings are handled by `str::lines`.
+fn normalize(input: &str) -> &str {
+    input.strip_prefix('\u{feff}').unwrap_or(input).trim_end()
+}
+
+/// A line of normalized input, remembering where it comes from for error reporting.
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    pub day: u8,
+    /// 1-based
+    pub number: usize,
+    /// Without trailing whitespace, leading whitespace is kept so that columns are right.
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    pub fn is_blank(&self) -> bool {
+        self.text.trim_start().is_empty()
+    }
+
+    /// Error on `token`, a subslice of this line.
+    pub fn error(&self, token: &str, expected: &'static str) -> ParseError {
+        ParseError::new(self.text, token, expected).at(self.day, self.number)
+    }
+
+    /// Parses the whole line, ignoring leading whitespace.
+    pub fn parse<T: FromStr<Err = ParseError>>(&self) -> Result<T, ParseError> {
+        let text = self.text.trim_start();
+        text.parse()
+            .map_err(|e: ParseError| e.within(self.text, text).at(self.day, self.number))
+    }
+
+    /// Comma separated fields, trimmed.
+    pub fn csv(&self) -> impl Iterator<Item = &'a str> {
+        self.text.split(',').map(str::trim)
+    }
+
+    pub fn csv_numbers<T: FromStr>(&self) -> Result<Vec<T>, ParseError> {
+        self.csv()
+            .map(|n| n.parse().map_err(|_| self.error(n, "number")))
+            .collect()
+    }
+}
+
+pub fn lines(day: u8, input: &str) -> impl Iterator<Item = Line<'_>> {
+    normalize(input)
+        .lines()
+        .enumerate()
+        .map(move |(i, text)| Line {
+            day,
+            number: i + 1,
+            text: text.trim_end(),
+        })
+}
+
+/// Every comma separated number of the input, whatever the lines they are on.
+pub fn csv_numbers<T: FromStr>(day: u8, input: &str) -> Result<Vec<T>, ParseError> {
+    lines(day, input)
+        .filter(|line| !line.is_blank())
+        .try_fold(vec![], |mut numbers, line| {
+            numbers.extend(line.csv_numbers()?);
+            Ok(numbers)
+        })
+}
+
+/// Groups of lines separated by one or more blank lines.
+pub fn blocks(day: u8, input: &str) -> Vec<Vec<Line<'_>>> {
+    let mut blocks: Vec<Vec<Line>> = vec![];
+    let mut previous_blank = true;
+    for line in lines(day, input) {
+        if line.is_blank() {
+            previous_blank = true;
+        } else {
+            if previous_blank {
+                blocks.push(vec![]);
+            }
+            blocks.last_mut().unwrap().push(line);
+            previous_blank = false;
+        }
+    }
+    blocks
+}
+
+/// Whitespace separated values, one row per line.
+pub fn grid<T: FromStr>(lines: &[Line]) -> Result<Vec<Vec<T>>, ParseError> {
+    lines
+        .iter()
+        .map(|line| {
+            line.text
+                .split_whitespace()
+                .map(|x| x.parse().map_err(|_| line.error(x, "value")))
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn normalizes_windows_input() {
+        let input = "\u{feff}1,2 \r\n\r\n\r\n 3\r\n4\t\r\n\r\n";
+        let blocks = blocks(1, input);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0][0].text, "1,2");
+        assert_eq!(blocks[1][1].number, 5);
+        assert_eq!(grid::<u8>(&blocks[1]).unwrap(), vec![vec![3], vec![4]]);
+        assert_eq!(csv_numbers::<u8>(1, input).unwrap(), vec![1, 2, 3, 4]);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod answer;
 pub mod day1;
 pub mod day2;
 pub mod day3;
@@ -6,6 +7,11 @@ pub mod day5;
 pub mod day6;
 pub mod day7;
 pub mod day8;
+pub mod error;
+pub mod gen;
+pub mod input;
+pub mod registry;
+pub mod report;
 
 use aoc_runner_derive::aoc_lib;
 
@@ -0,0 +1,135 @@
+use std::{any::Any, error::Error};
+
+use crate::{
+    answer::{Answer, IntoAnswer},
+    day1, day2, day3, day4, day5, day6, day7, day8,
+    error::ParseError,
+};
+
+/// A puzzle part, erased from its generator output type so that every part can be
+/// stored in the same registry.
+pub trait Solution: Sync {
+    fn name(&self) -> &'static str;
+
+    fn day(&self) -> u8;
+
+    fn part(&self) -> u8;
+
+    /// Name of an alternative solver, `None` for the main one of its part.
+    fn variant(&self) -> Option<&'static str>;
+
+    fn generate(&self, input: &str) -> Result<Box<dyn Any>, ParseError>;
+
+    /// Panics if `input` was not produced by this solution's `generate`.
+    fn solve(&self, input: &dyn Any) -> Result<Answer, Box<dyn Error>>;
+
+    fn run(&self, input: &str) -> Result<Answer, Box<dyn Error>> {
+        let input = self.generate(input)?;
+        self.solve(input.as_ref())
+    }
+}
+
+struct DaySolution<T> {
+    name: &'static str,
+    day: u8,
+    part: u8,
+    variant: Option<&'static str>,
+    generator: fn(&str) -> Result<T, ParseError>,
+    solver: fn(&T) -> Result<Answer, Box<dyn Error>>,
+}
+
+impl<T: 'static> Solution for DaySolution<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn day(&self) -> u8 {
+        self.day
+    }
+
+    fn part(&self) -> u8 {
+        self.part
+    }
+
+    fn variant(&self) -> Option<&'static str> {
+        self.variant
+    }
+
+    fn generate(&self, input: &str) -> Result<Box<dyn Any>, ParseError> {
+        Ok(Box::new((self.generator)(input)?))
+    }
+
+    fn solve(&self, input: &dyn Any) -> Result<Answer, Box<dyn Error>> {
+        let input = input
+            .downcast_ref::<T>()
+            .unwrap_or_else(|| panic!("{} called with another day's input", self.name));
+        (self.solver)(input)
+    }
+}
+
+macro_rules! solution {
+    ($day:ident, $n:literal, $solver:ident, $part:literal) => {
+        solution!($day, $n, $solver, $part, None)
+    };
+    ($day:ident, $n:literal, $solver:ident, $part:literal, $variant:expr) => {
+        &DaySolution {
+            name: concat!(stringify!($day), "::", stringify!($solver)),
+            day: $n,
+            part: $part,
+            variant: $variant,
+            generator: $day::input_generator,
+            solver: |input| $day::$solver(input).into_answer(),
+        }
+    };
+}
+
+static REGISTRY: [&dyn Solution; 18] = [
+    solution!(day1, 1, solve_part1, 1),
+    solution!(day1, 1, solve_part2, 2),
+    solution!(day2, 2, solve_part1, 1),
+    solution!(day2, 2, solve_part2, 2),
+    solution!(day3, 3, solve_part1, 1),
+    solution!(day3, 3, solve_part2, 2),
+    solution!(day4, 4, solve_part1, 1),
+    solution!(day4, 4, solve_part2, 2),
+    solution!(day5, 5, solve_part1, 1),
+    solution!(day5, 5, solve_part2, 2),
+    solution!(day6, 6, solve_part1, 1),
+    solution!(day6, 6, solve_part2, 2),
+    solution!(day7, 7, solve_part1, 1),
+    solution!(day7, 7, solve_part1_median, 1, Some("median")),
+    solution!(day7, 7, solve_part2, 2),
+    solution!(day7, 7, solve_part2_mean, 2, Some("mean")),
+    solution!(day8, 8, solve_part1, 1),
+    solution!(day8, 8, solve_part2, 2),
+];
+
+/// Every implemented part, sorted by day then part, alternative solvers after the main one.
+pub fn registry() -> &'static [&'static dyn Solution] {
+    &REGISTRY
+}
+
+/// The main solver of a part.
+pub fn find(day: u8, part: u8) -> Option<&'static dyn Solution> {
+    registry()
+        .iter()
+        .copied()
+        .find(|s| s.day() == day && s.part() == part && s.variant().is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn registry_runs_every_part() {
+        let answer = find(7, 2).unwrap().run("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!(answer, Answer::Unsigned(168));
+        assert_eq!(registry().len(), 18);
+        let median = registry()
+            .iter()
+            .find(|s| s.variant() == Some("median"))
+            .unwrap();
+        assert_eq!((median.day(), median.part()), (7, 1));
+        assert_eq!(find(7, 1).unwrap().name(), "day7::solve_part1");
+    }
+}
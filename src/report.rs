@@ -0,0 +1,169 @@
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{answer::Answer, registry::Solution};
+
+/// Outcome of one solution run, in a shape dashboards can ingest.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub day: u8,
+    pub part: u8,
+    pub variant: Option<&'static str>,
+    pub name: &'static str,
+    pub answer: Result<Answer, String>,
+    pub generator_time: Duration,
+    pub solver_time: Duration,
+    pub input_hash: u64,
+}
+
+/// FNV-1a, stable across platforms and compiler versions unlike `DefaultHasher`.
+pub fn hash(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl Report {
+    pub fn run(solution: &dyn Solution, input: &str) -> Self {
+        let start = Instant::now();
+        let generated = solution.generate(input);
+        let generator_time = start.elapsed();
+        let start = Instant::now();
+        let answer = generated
+            .map_err(|e| e.to_string())
+            .and_then(|input| solution.solve(input.as_ref()).map_err(|e| e.to_string()));
+        let solver_time = start.elapsed();
+        Self {
+            day: solution.day(),
+            part: solution.part(),
+            variant: solution.variant(),
+            name: solution.name(),
+            answer,
+            generator_time,
+            solver_time,
+            input_hash: hash(input),
+        }
+    }
+
+    /// For when there is no input to run on, e.g. the file can't be read.
+    pub fn failed(solution: &dyn Solution, error: String) -> Self {
+        Self {
+            day: solution.day(),
+            part: solution.part(),
+            variant: solution.variant(),
+            name: solution.name(),
+            answer: Err(error),
+            generator_time: Duration::ZERO,
+            solver_time: Duration::ZERO,
+            input_hash: 0,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.answer.is_ok()
+    }
+
+    fn status(&self) -> &'static str {
+        if self.is_ok() {
+            "ok"
+        } else {
+            "error"
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let part = match self.variant {
+            Some(variant) => format!("day{} part{} ({})", self.day, self.part, variant),
+            None => format!("day{} part{}", self.day, self.part),
+        };
+        match &self.answer {
+            Ok(answer) => format!("{}: {}", part, answer),
+            Err(e) => format!("{}: error: {}", part, e),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let (answer, error) = match &self.answer {
+            Ok(answer) => (json_string(&answer.to_string()), "null".to_owned()),
+            Err(e) => ("null".to_owned(), json_string(e)),
+        };
+        format!(
+            "{{\"day\":{},\"part\":{},\"name\":{},\"status\":\"{}\",\"answer\":{},\"error\":{},\"generator_ns\":{},\"solver_ns\":{},\"input_hash\":\"{:016x}\"}}",
+            self.day,
+            self.part,
+            json_string(self.name),
+            self.status(),
+            answer,
+            error,
+            self.generator_time.as_nanos(),
+            self.solver_time.as_nanos(),
+            self.input_hash
+        )
+    }
+
+    pub const CSV_HEADER: &'static str =
+        "day,part,name,status,answer,error,generator_ns,solver_ns,input_hash";
+
+    pub fn to_csv(&self) -> String {
+        let (answer, error) = match &self.answer {
+            Ok(answer) => (csv_field(&answer.to_string()), String::new()),
+            Err(e) => (String::new(), csv_field(e)),
+        };
+        format!(
+            "{},{},{},{},{},{},{},{},{:016x}",
+            self.day,
+            self.part,
+            self.name,
+            self.status(),
+            answer,
+            error,
+            self.generator_time.as_nanos(),
+            self.solver_time.as_nanos(),
+            self.input_hash
+        )
+    }
+}
+
+/// Answers are always strings, u128 does not fit in a JSON number.
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::find;
+    #[test]
+    fn exports_errors() {
+        let report = Report::run(find(7, 1).unwrap(), "16,x");
+        assert!(report.to_json().contains(
+            "\"status\":\"error\",\"answer\":null,\"error\":\"day7 line 1 column 4: expected `number`, found `x`\""
+        ));
+        assert!(report.to_csv().starts_with(
+            "7,1,day7::solve_part1,error,,\"day7 line 1 column 4: expected `number`, found `x`\","
+        ));
+    }
+}
@@ -0,0 +1,66 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use aoc2021::registry::registry;
+
+/// Reads the `[dayN]` tables of `answers.toml`, keyed by (day, part).
+fn expected_answers(path: &Path) -> BTreeMap<(u8, u8), String> {
+    let manifest = fs::read_to_string(path).unwrap();
+    let mut answers = BTreeMap::new();
+    let mut day = None;
+    for line in manifest.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(table) = line.strip_prefix("[day").and_then(|l| l.strip_suffix(']')) {
+            day = Some(table.parse().unwrap());
+        } else {
+            let (key, value) = line.split_once('=').unwrap();
+            let part = key.trim().strip_prefix("part").unwrap().parse().unwrap();
+            let value = value.trim().trim_matches('"').to_owned();
+            answers.insert((day.unwrap(), part), value);
+        }
+    }
+    answers
+}
+
+#[test]
+fn bundled_inputs_give_expected_answers() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("input/2021");
+    let expected = expected_answers(&dir.join("answers.toml"));
+
+    let mut failures = vec![];
+    for solution in registry() {
+        let input = fs::read_to_string(dir.join(format!("day{}.txt", solution.day()))).unwrap();
+        let actual = match solution.run(input.trim_end_matches('\n')) {
+            Ok(answer) => answer.to_string(),
+            Err(e) => e.to_string(),
+        };
+        let expected = expected
+            .get(&(solution.day(), solution.part()))
+            .map(String::as_str)
+            .unwrap_or("<missing>");
+        if actual != expected {
+            failures.push((
+                solution.day(),
+                solution.part(),
+                solution.name(),
+                expected.to_owned(),
+                actual,
+            ));
+        }
+    }
+
+    if !failures.is_empty() {
+        let mut table = format!(
+            "{:<5} {:<5} {:<26} {:<20} {:<20}\n",
+            "day", "part", "solver", "expected", "actual"
+        );
+        for (day, part, name, expected, actual) in &failures {
+            table += &format!(
+                "{:<5} {:<5} {:<26} {:<20} {:<20}\n",
+                day, part, name, expected, actual
+            );
+        }
+        panic!("{} answer(s) differ:\n{}", failures.len(), table);
+    }
+}